@@ -0,0 +1,115 @@
+use crate::auth::key_pair::KeyPair;
+use crate::auth::Authenticator;
+use crate::errors::SnowflakeError;
+
+use async_trait::async_trait;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Login material sent to Snowflake when a session is created.
+#[derive(Clone)]
+pub enum Credentials {
+    /// User and password, optionally with an MFA passcode.
+    Password { password: String, passcode: Option<String> },
+    /// Private key used to sign a JWT for key pair authentication.
+    KeyPair(KeyPair),
+    /// OAuth access token obtained from the identity provider.
+    OAuth(String),
+}
+
+impl Credentials {
+    pub fn password(password: &str) -> Self {
+        Credentials::Password {
+            password: password.to_owned(),
+            passcode: None,
+        }
+    }
+
+    /// Password with an MFA passcode, used instead of waiting for a Duo push.
+    pub fn password_with_passcode(password: &str, passcode: &str) -> Self {
+        Credentials::Password {
+            password: password.to_owned(),
+            passcode: Some(passcode.to_owned()),
+        }
+    }
+
+    pub fn authenticator(&self) -> Authenticator {
+        match self {
+            Credentials::Password { .. } => Authenticator::Snowflake,
+            Credentials::KeyPair(_) => Authenticator::SnowflakeJwt,
+            Credentials::OAuth(_) => Authenticator::OAuth,
+        }
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Credentials::Password { passcode, .. } => f
+                .debug_struct("Password")
+                .field("password", &"<redacted>")
+                .field("passcode", &passcode.as_ref().map(|_| "<redacted>"))
+                .finish(),
+            Credentials::KeyPair(key) => f.debug_tuple("KeyPair").field(key).finish(),
+            Credentials::OAuth(_) => f.debug_tuple("OAuth").field(&"<redacted>").finish(),
+        }
+    }
+}
+
+/// Source of login material, called every time a session is created.
+///
+/// Implement this to fetch secrets from Vault, AWS Secrets Manager or similar
+/// instead of keeping them in the client.
+#[async_trait]
+pub trait CredentialProvider: fmt::Debug + Send + Sync {
+    async fn credentials(&self) -> Result<Credentials, SnowflakeError>;
+}
+
+/// Provider returning the same credentials for every session.
+#[derive(Debug)]
+pub struct StaticCredentials(Credentials);
+
+impl StaticCredentials {
+    pub fn new(credentials: Credentials) -> Self {
+        Self(credentials)
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for StaticCredentials {
+    async fn credentials(&self) -> Result<Credentials, SnowflakeError> {
+        Ok(self.0.clone())
+    }
+}
+
+/// Provider reading a PEM encoded private key from disk for every session, so rotated keys are picked up.
+pub struct PrivateKeyFile {
+    path: PathBuf,
+    passphrase: Option<String>,
+}
+
+impl PrivateKeyFile {
+    pub fn new(path: impl Into<PathBuf>, passphrase: Option<&str>) -> Self {
+        Self {
+            path: path.into(),
+            passphrase: passphrase.map(str::to_owned),
+        }
+    }
+}
+
+impl fmt::Debug for PrivateKeyFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrivateKeyFile")
+            .field("path", &self.path)
+            .field("passphrase", &self.passphrase.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for PrivateKeyFile {
+    async fn credentials(&self) -> Result<Credentials, SnowflakeError> {
+        let key = KeyPair::from_file(&self.path, self.passphrase.as_deref())?;
+        Ok(Credentials::KeyPair(key))
+    }
+}
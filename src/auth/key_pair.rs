@@ -0,0 +1,170 @@
+use crate::errors::SnowflakeError;
+
+use anyhow::anyhow;
+use base64::engine::fast_portable::{FastPortable, NO_PAD};
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::pkcs1v15::SigningKey;
+use rsa::pkcs8::{DecodePrivateKey, EncodePublicKey};
+use rsa::sha2::{Digest, Sha256};
+use rsa::signature::{SignatureEncoding, Signer};
+use rsa::RsaPrivateKey;
+use serde::Serialize;
+use serde_json::json;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default lifetime of the JWT sent to Snowflake. Snowflake rejects tokens valid for more than an hour.
+pub const DEFAULT_JWT_LIFETIME: Duration = Duration::from_secs(60);
+
+const URL_SAFE_NO_PAD: FastPortable = FastPortable::from(&base64::alphabet::URL_SAFE, NO_PAD);
+
+#[derive(Serialize)]
+struct Claims<'a> {
+    iss: &'a str,
+    sub: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+/// RSA key pair used for `SNOWFLAKE_JWT` authentication.
+#[derive(Clone)]
+pub struct KeyPair {
+    private_key: Arc<RsaPrivateKey>,
+    fingerprint: String,
+}
+
+impl KeyPair {
+    /// Loads a PKCS#8 or PKCS#1 PEM encoded private key.
+    ///
+    /// Encrypted PKCS#8 keys (`BEGIN ENCRYPTED PRIVATE KEY`) require a passphrase.
+    pub fn from_pem(pem: &str, passphrase: Option<&str>) -> Result<Self, SnowflakeError> {
+        let private_key = if pem.contains("BEGIN ENCRYPTED PRIVATE KEY") {
+            let passphrase = passphrase.ok_or_else(|| {
+                SnowflakeError::AuthenticationError(anyhow!("Private key is encrypted, but no passphrase was given"))
+            })?;
+            RsaPrivateKey::from_pkcs8_encrypted_pem(pem, passphrase)
+                .map_err(|e| SnowflakeError::AuthenticationError(anyhow!("Failed to decrypt private key: {e}")))?
+        }
+        else if pem.contains("BEGIN RSA PRIVATE KEY") {
+            RsaPrivateKey::from_pkcs1_pem(pem)
+                .map_err(|e| SnowflakeError::AuthenticationError(anyhow!("Failed to parse private key: {e}")))?
+        }
+        else {
+            RsaPrivateKey::from_pkcs8_pem(pem)
+                .map_err(|e| SnowflakeError::AuthenticationError(anyhow!("Failed to parse private key: {e}")))?
+        };
+
+        let public_key = private_key
+            .to_public_key()
+            .to_public_key_der()
+            .map_err(|e| SnowflakeError::AuthenticationError(e.into()))?;
+        let fingerprint = format!("SHA256:{}", base64::encode(Sha256::digest(public_key.as_bytes())));
+
+        Ok(Self {
+            private_key: Arc::new(private_key),
+            fingerprint,
+        })
+    }
+
+    /// Reads a PEM encoded private key from disk. See [`KeyPair::from_pem`].
+    pub fn from_file(path: impl AsRef<Path>, passphrase: Option<&str>) -> Result<Self, SnowflakeError> {
+        let path = path.as_ref();
+        let pem = std::fs::read_to_string(path).map_err(|e| {
+            SnowflakeError::AuthenticationError(anyhow!("Failed to read private key {}: {e}", path.display()))
+        })?;
+        Self::from_pem(&pem, passphrase)
+    }
+
+    /// Public key fingerprint in the form Snowflake stores in `RSA_PUBLIC_KEY_FP`.
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// Generates a signed RS256 token for the given account and user.
+    ///
+    /// The account should not include the region, i.e. `xy12345` rather than `xy12345.us-central1.gcp`.
+    pub fn generate_jwt(&self, account: &str, user: &str, lifetime: Duration) -> Result<String, SnowflakeError> {
+        let qualified_user = format!("{}.{}", account.to_ascii_uppercase(), user.to_ascii_uppercase());
+        let issuer = format!("{qualified_user}.{}", self.fingerprint);
+        let now = chrono::Utc::now().timestamp();
+
+        let claims = Claims {
+            iss: &issuer,
+            sub: &qualified_user,
+            iat: now,
+            exp: now + lifetime.as_secs() as i64,
+        };
+
+        let header = json!({ "alg": "RS256", "typ": "JWT" });
+        let header = serde_json::to_vec(&header).map_err(|e| SnowflakeError::SerializationError(e.into()))?;
+        let claims = serde_json::to_vec(&claims).map_err(|e| SnowflakeError::SerializationError(e.into()))?;
+        let message = format!(
+            "{}.{}",
+            base64::encode_engine(header, &URL_SAFE_NO_PAD),
+            base64::encode_engine(claims, &URL_SAFE_NO_PAD)
+        );
+
+        let signing_key = SigningKey::<Sha256>::new(self.private_key.as_ref().clone());
+        let signature = signing_key
+            .try_sign(message.as_bytes())
+            .map_err(|e| SnowflakeError::AuthenticationError(e.into()))?;
+
+        Ok(format!(
+            "{message}.{}",
+            base64::encode_engine(signature.to_bytes(), &URL_SAFE_NO_PAD)
+        ))
+    }
+}
+
+impl fmt::Debug for KeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyPair")
+            .field("fingerprint", &self.fingerprint)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIVATE_KEY: &str = include_str!("../../tests/data/rsa_key.p8");
+    const ENCRYPTED_PRIVATE_KEY: &str = include_str!("../../tests/data/rsa_key_encrypted.p8");
+    const FINGERPRINT: &str = "SHA256:wmLBDt0CiNip1vfp1Z4ftJ1WcbNarS5VDl7rmGZ3pBA=";
+
+    #[test]
+    fn test_load_private_key() {
+        let key = KeyPair::from_pem(PRIVATE_KEY, None).expect("Key should have been loaded");
+        assert_eq!(key.fingerprint(), FINGERPRINT);
+
+        let key = KeyPair::from_pem(ENCRYPTED_PRIVATE_KEY, Some("test_passphrase"))
+            .expect("Encrypted key should have been loaded");
+        assert_eq!(key.fingerprint(), FINGERPRINT);
+
+        KeyPair::from_pem(ENCRYPTED_PRIVATE_KEY, None).expect_err("Should have failed due to missing passphrase");
+        KeyPair::from_pem(ENCRYPTED_PRIVATE_KEY, Some("wrong"))
+            .expect_err("Should have failed due to wrong passphrase");
+    }
+
+    #[test]
+    fn test_generate_jwt() {
+        let key = KeyPair::from_pem(PRIVATE_KEY, None).unwrap();
+        let token = key
+            .generate_jwt("my_account", "my_user", DEFAULT_JWT_LIFETIME)
+            .expect("Token should have been generated");
+
+        let parts: Vec<&str> = token.split('.').collect();
+        assert_eq!(parts.len(), 3);
+
+        let claims: serde_json::Value =
+            serde_json::from_slice(&base64::decode_engine(parts[1], &URL_SAFE_NO_PAD).unwrap()).unwrap();
+        assert_eq!(claims["sub"], "MY_ACCOUNT.MY_USER");
+        assert_eq!(claims["iss"], format!("MY_ACCOUNT.MY_USER.{FINGERPRINT}"));
+        assert_eq!(
+            claims["exp"].as_i64().unwrap() - claims["iat"].as_i64().unwrap(),
+            DEFAULT_JWT_LIFETIME.as_secs() as i64
+        );
+    }
+}
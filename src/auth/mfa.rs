@@ -0,0 +1,46 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// MFA token returned by Snowflake after a successful MFA login.
+///
+/// Sending it with the next login skips the Duo push for as long as the account allows MFA token caching.
+#[derive(Clone, Default)]
+pub(crate) struct MfaTokenCache(Arc<Mutex<Option<String>>>);
+
+impl MfaTokenCache {
+    pub(crate) fn get(&self) -> Option<String> {
+        self.0.lock().expect("MFA token cache should not be poisoned").clone()
+    }
+
+    pub(crate) fn set(&self, token: Option<String>) {
+        *self.0.lock().expect("MFA token cache should not be poisoned") = token;
+    }
+}
+
+impl fmt::Debug for MfaTokenCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MfaTokenCache")
+            .field(&self.get().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_mfa_token_cache() {
+        let cache = MfaTokenCache::default();
+        assert_eq!(cache.get(), None);
+
+        let shared = cache.clone();
+        shared.set(Some("mfa_token".to_owned()));
+        assert_eq!(cache.get(), Some("mfa_token".to_owned()));
+        assert!(!format!("{cache:?}").contains("mfa_token"));
+
+        cache.set(None);
+        assert_eq!(shared.get(), None);
+    }
+}
@@ -0,0 +1,48 @@
+pub mod credentials;
+pub mod key_pair;
+pub(crate) mod mfa;
+pub(crate) mod tokens;
+
+use crate::errors::SnowflakeError;
+
+use anyhow::anyhow;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Authenticator {
+    /// User and password authentication.
+    #[default]
+    Snowflake,
+    /// User and password authentication with MFA token caching.
+    UsernamePasswordMfa,
+    /// Key pair authentication using a signed JWT.
+    SnowflakeJwt,
+    /// OAuth authentication using an access token.
+    OAuth,
+}
+
+impl Authenticator {
+    /// Value sent as `AUTHENTICATOR` in the login request, if any.
+    pub(crate) fn login_name(&self) -> Option<&'static str> {
+        match self {
+            Authenticator::Snowflake => None,
+            Authenticator::UsernamePasswordMfa => Some("USERNAME_PASSWORD_MFA"),
+            Authenticator::SnowflakeJwt => Some("SNOWFLAKE_JWT"),
+            Authenticator::OAuth => Some("OAUTH"),
+        }
+    }
+}
+
+impl FromStr for Authenticator {
+    type Err = SnowflakeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "snowflake" => Ok(Authenticator::Snowflake),
+            "username_password_mfa" => Ok(Authenticator::UsernamePasswordMfa),
+            "snowflake_jwt" => Ok(Authenticator::SnowflakeJwt),
+            "oauth" => Ok(Authenticator::OAuth),
+            x => Err(SnowflakeError::GeneralError(anyhow!("Unsupported authenticator '{x}'"))),
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use crate::errors::SnowflakeError;
+use crate::requests::TokenRequest;
+use crate::responses::types::{data::DataResponse, login::TokenRenewalResponse};
+
+use anyhow::anyhow;
+use reqwest::header::AUTHORIZATION;
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub(crate) const SESSION_GONE_CODE: &str = "390111";
+pub(crate) const SESSION_TOKEN_EXPIRED_CODE: &str = "390112";
+
+struct SessionTokens {
+    token: String,
+    master_token: Option<String>,
+}
+
+/// Session and master tokens of a logged in session.
+///
+/// Cloning is cheap and clones share the same tokens, so a renewal is seen by every request of the session.
+#[derive(Clone)]
+pub(crate) struct TokenManager {
+    client: reqwest::Client,
+    host: String,
+    tokens: Arc<RwLock<Option<SessionTokens>>>,
+}
+
+impl TokenManager {
+    pub(crate) fn new(client: reqwest::Client, host: &str) -> Self {
+        Self {
+            client,
+            host: host.to_owned(),
+            tokens: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Sets the tokens for every clone of the manager.
+    pub(crate) async fn set_tokens(&self, token: &str, master_token: Option<&str>) {
+        *self.tokens.write().await = Some(SessionTokens {
+            token: token.to_owned(),
+            master_token: master_token.map(str::to_owned),
+        });
+    }
+
+    pub(crate) async fn session_token(&self) -> Option<String> {
+        self.tokens.read().await.as_ref().map(|t| t.token.clone())
+    }
+
+    /// Sets the current session token on the request, overriding the one the client was created with.
+    pub(crate) async fn authorize(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match self.tokens.read().await.as_ref() {
+            Some(tokens) => builder.header(AUTHORIZATION, Self::get_authorization_header(&tokens.token)),
+            None => builder,
+        }
+    }
+
+    /// Exchanges the master token for a new session token.
+    ///
+    /// Does nothing if the session was already renewed by a concurrent request.
+    pub(crate) async fn renew(&self, expired_token: &str) -> Result<(), SnowflakeError> {
+        let mut guard = self.tokens.write().await;
+        let tokens = match guard.as_mut() {
+            Some(tokens) if tokens.token != expired_token => return Ok(()),
+            Some(tokens) => tokens,
+            None => {
+                return Err(SnowflakeError::AuthenticationError(anyhow!(
+                    "Session token expired, but session cannot be renewed"
+                )))
+            }
+        };
+
+        let master_token = tokens.master_token.as_deref().ok_or_else(|| {
+            SnowflakeError::AuthenticationError(anyhow!("Session token expired, but no master token is available"))
+        })?;
+
+        let req = TokenRequest {
+            old_session_token: expired_token,
+            request_type: "RENEW",
+        };
+
+        let body = self
+            .client
+            .post(self.get_token_request_url())
+            .header(AUTHORIZATION, Self::get_authorization_header(master_token))
+            .json(&req)
+            .send()
+            .await
+            .map_err(|e| SnowflakeError::AuthenticationError(e.into()))?;
+
+        let text = body
+            .text()
+            .await
+            .map_err(|e| SnowflakeError::AuthenticationError(e.into()))?;
+
+        let res: DataResponse<serde_json::Value> = serde_json::from_str(&text).map_err(|e| {
+            log::error!("Failed to renew session due to deserialization error.");
+            SnowflakeError::new_deserialization_error_with_value(e.into(), text.to_owned())
+        })?;
+
+        if !res.success {
+            return Err(SnowflakeError::AuthenticationError(anyhow!(res
+                .message
+                .unwrap_or_else(|| {
+                    "Failed to renew session, but no reason was given by Snowflake API".to_owned()
+                }))));
+        }
+
+        let data: TokenRenewalResponse = serde_json::from_value(res.data)
+            .map_err(|e| SnowflakeError::new_deserialization_error_with_value(e.into(), text))?;
+
+        tokens.token = data.session_token;
+        if let Some(master_token) = data.master_token {
+            tokens.master_token = Some(master_token);
+        }
+        log::debug!("Session renewed.");
+        Ok(())
+    }
+
+    fn get_authorization_header(token: &str) -> String {
+        format!("Snowflake Token=\"{token}\"")
+    }
+
+    fn get_token_request_url(&self) -> String {
+        let uuid = uuid::Uuid::new_v4();
+        let guid = uuid::Uuid::new_v4();
+        let url = format!(
+            "{}/session/token-request?requestId={uuid}&request_guid={guid}",
+            self.host
+        );
+        log::debug!("Using token request url {url}");
+        url
+    }
+}
+
+impl fmt::Debug for TokenManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenManager")
+            .field("host", &self.host)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_set_tokens_shared() {
+        let tokens = TokenManager::new(reqwest::Client::new(), "http://127.0.0.1:1");
+        let clone = tokens.clone();
+        assert_eq!(clone.session_token().await, None);
+
+        tokens.set_tokens("session", Some("master")).await;
+        assert_eq!(clone.session_token().await.as_deref(), Some("session"));
+    }
+}
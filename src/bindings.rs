@@ -0,0 +1,269 @@
+use crate::errors::SnowflakeError;
+use crate::responses::types::value::Value;
+
+use anyhow::anyhow;
+
+use chrono::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Number of values Snowflake accepts inline in a single request, equivalent to the default of
+/// `CLIENT_STAGE_ARRAY_BINDING_THRESHOLD`.
+pub const ARRAY_BINDING_THRESHOLD: usize = 65280;
+
+/// Values bound to the placeholders of a query instead of being formatted into the SQL text.
+#[derive(Clone, Debug)]
+pub enum Params {
+    /// Bound in order to `?` or `:1`, `:2`, ... placeholders.
+    Positional(Vec<Value>),
+    /// Bound by name to `:name` placeholders.
+    Named(HashMap<String, Value>),
+}
+
+impl Params {
+    pub(crate) fn to_bindings(&self) -> HashMap<String, Binding> {
+        match self {
+            Params::Positional(values) => values
+                .iter()
+                .enumerate()
+                .map(|(i, value)| ((i + 1).to_string(), Binding::from_value(value)))
+                .collect(),
+            Params::Named(values) => values
+                .iter()
+                .map(|(name, value)| (name.clone(), Binding::from_value(value)))
+                .collect(),
+        }
+    }
+}
+
+impl From<Vec<Value>> for Params {
+    fn from(values: Vec<Value>) -> Self {
+        Params::Positional(values)
+    }
+}
+
+impl From<HashMap<String, Value>> for Params {
+    fn from(values: HashMap<String, Value>) -> Self {
+        Params::Named(values)
+    }
+}
+
+/// Binds every row of `rows` at once, column-wise, so a single statement is executed for all of them.
+pub(crate) fn array_bindings(rows: &[Vec<Value>]) -> Result<HashMap<String, Binding>, SnowflakeError> {
+    let columns = column_count(rows)?;
+    (0..columns)
+        .map(|col| {
+            let mut binding_type = None;
+            let mut values = Vec::with_capacity(rows.len());
+            for (i, row) in rows.iter().enumerate() {
+                let (value_type, value) = to_snowflake(&row[col]);
+                if value.is_some() {
+                    match binding_type {
+                        Some(t) if t != value_type => {
+                            return Err(SnowflakeError::GeneralError(anyhow!(
+                                "Mixed types in column {}: expected {t}, but row {i} is {value_type}",
+                                col + 1
+                            )))
+                        }
+                        _ => binding_type = Some(value_type),
+                    }
+                }
+                values.push(value);
+            }
+
+            let binding = Binding {
+                binding_type: binding_type.unwrap_or("TEXT"),
+                value: BindingValue::Array(values),
+            };
+            Ok(((col + 1).to_string(), binding))
+        })
+        .collect()
+}
+
+/// Number of values in every row of `rows`, which must all be of the same length.
+pub(crate) fn column_count(rows: &[Vec<Value>]) -> Result<usize, SnowflakeError> {
+    let columns = rows.first().map_or(0, Vec::len);
+    match rows.iter().find(|row| row.len() != columns) {
+        Some(row) => Err(SnowflakeError::GeneralError(anyhow!(
+            "Expected {columns} values in every row, but found a row with {}",
+            row.len()
+        ))),
+        None => Ok(columns),
+    }
+}
+
+/// A bound value as sent in the `bindings` field of a query request.
+///
+/// Values are sent as strings in the format Snowflake expects for the type tag.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Binding {
+    #[serde(rename = "type")]
+    pub binding_type: &'static str,
+    pub value: BindingValue,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum BindingValue {
+    Single(Option<String>),
+    /// One value per row for array bindings.
+    Array(Vec<Option<String>>),
+}
+
+impl Binding {
+    pub fn from_value(value: &Value) -> Self {
+        let (binding_type, value) = to_snowflake(value);
+        Binding {
+            binding_type,
+            value: BindingValue::Single(value),
+        }
+    }
+}
+
+/// Type tag and string representation of `value`, or no value for NULL.
+fn to_snowflake(value: &Value) -> (&'static str, Option<String>) {
+    let (binding_type, value) = match value {
+        Value::Binary(v) => ("BINARY", hex::encode(v)),
+        Value::Boolean(v) => ("BOOLEAN", v.to_string()),
+        Value::Decimal(v) => ("FIXED", v.to_string()),
+        Value::I128(v) => ("FIXED", v.to_string()),
+        Value::I64(v) => ("FIXED", v.to_string()),
+        Value::Float(v) => ("REAL", v.to_string()),
+        Value::String(v) => ("TEXT", v.clone()),
+        Value::NaiveDate(v) => (
+            "DATE",
+            (v.and_time(NaiveTime::MIN).and_utc().timestamp() * 1000).to_string(),
+        ),
+        Value::NaiveTime(v) => ("TIME", time_nanos(v).to_string()),
+        Value::NaiveDateTime(v) => ("TIMESTAMP_NTZ", epoch_nanos(&v.and_utc()).to_string()),
+        Value::DateTimeUTC(v) => ("TIMESTAMP_TZ", format!("{} 1440", epoch_nanos(v))),
+        Value::DateTime(v) => (
+            "TIMESTAMP_TZ",
+            // Snowflake expects the offset in minutes, shifted by a day to keep it positive
+            format!("{} {}", epoch_nanos(v), v.offset().local_minus_utc() / 60 + 1440),
+        ),
+        Value::HashMap(v) | Value::Geography(v) | Value::Geometry(v) => ("TEXT", json_text(&v)),
+        Value::Vec(v) => ("TEXT", json_text(&v)),
+        Value::Variant(v) | Value::Unsupported(v) => match v {
+            serde_json::Value::String(s) => ("TEXT", s.clone()),
+            v => ("TEXT", v.to_string()),
+        },
+        Value::Nullable(Some(v)) => return to_snowflake(v),
+        Value::Nullable(None) => return ("ANY", None),
+    };
+    (binding_type, Some(value))
+}
+
+fn epoch_nanos<Tz: TimeZone>(dt: &DateTime<Tz>) -> i128 {
+    dt.timestamp() as i128 * 1_000_000_000 + dt.timestamp_subsec_nanos() as i128
+}
+
+fn time_nanos(t: &NaiveTime) -> i64 {
+    t.num_seconds_from_midnight() as i64 * 1_000_000_000 + t.nanosecond() as i64
+}
+
+fn json_text<T: Serialize>(v: &T) -> String {
+    serde_json::to_string(v).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_binding_from_value() {
+        let binding = |v: Value| match Binding::from_value(&v) {
+            Binding {
+                binding_type,
+                value: BindingValue::Single(value),
+            } => (binding_type, value),
+            b => panic!("Unexpected binding {b:?}"),
+        };
+
+        assert_eq!(binding(Value::I64(42)), ("FIXED", Some("42".to_owned())));
+        assert_eq!(binding(Value::Float(1.5)), ("REAL", Some("1.5".to_owned())));
+        assert_eq!(binding(Value::Boolean(true)), ("BOOLEAN", Some("true".to_owned())));
+        assert_eq!(
+            binding(Value::Binary(vec![0xab, 0x01])),
+            ("BINARY", Some("ab01".to_owned()))
+        );
+        assert_eq!(
+            binding(Value::String("it's".to_owned())),
+            ("TEXT", Some("it's".to_owned()))
+        );
+
+        let date = NaiveDate::from_ymd_opt(2023, 1, 2).unwrap();
+        assert_eq!(
+            binding(Value::NaiveDate(date)),
+            ("DATE", Some("1672617600000".to_owned()))
+        );
+
+        let time = NaiveTime::from_hms_nano_opt(1, 2, 3, 4).unwrap();
+        assert_eq!(
+            binding(Value::NaiveTime(time)),
+            ("TIME", Some("3723000000004".to_owned()))
+        );
+
+        let datetime = date.and_time(time);
+        assert_eq!(
+            binding(Value::NaiveDateTime(datetime)),
+            ("TIMESTAMP_NTZ", Some("1672621323000000004".to_owned()))
+        );
+        assert_eq!(
+            binding(Value::DateTimeUTC(datetime.and_utc())),
+            ("TIMESTAMP_TZ", Some("1672621323000000004 1440".to_owned()))
+        );
+
+        let offset = FixedOffset::east_opt(-2 * 3600).unwrap();
+        let local = offset.from_utc_datetime(&datetime);
+        assert_eq!(
+            binding(Value::DateTime(local)),
+            ("TIMESTAMP_TZ", Some("1672621323000000004 1320".to_owned()))
+        );
+
+        assert_eq!(
+            binding(Value::Nullable(Some(Box::new(Value::I64(1))))),
+            ("FIXED", Some("1".to_owned()))
+        );
+        assert_eq!(binding(Value::Nullable(None)), ("ANY", None));
+    }
+
+    #[test]
+    fn test_params_to_bindings() {
+        let bindings = Params::from(vec![Value::I64(1), Value::String("a".to_owned())]).to_bindings();
+        assert_eq!(bindings.len(), 2);
+        assert_eq!(bindings["1"].binding_type, "FIXED");
+        assert_eq!(bindings["2"].value, BindingValue::Single(Some("a".to_owned())));
+
+        let named = HashMap::from([("name".to_owned(), Value::Boolean(false))]);
+        let bindings = Params::from(named).to_bindings();
+        assert_eq!(bindings["name"].binding_type, "BOOLEAN");
+
+        let json = serde_json::to_value(&bindings).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "name": { "type": "BOOLEAN", "value": "false" } })
+        );
+    }
+
+    #[test]
+    fn test_array_bindings() {
+        let rows = vec![
+            vec![Value::I64(1), Value::String("a".to_owned())],
+            vec![Value::I64(2), Value::Nullable(None)],
+        ];
+        let bindings = array_bindings(&rows).unwrap();
+        assert_eq!(
+            serde_json::to_value(&bindings).unwrap(),
+            serde_json::json!({
+                "1": { "type": "FIXED", "value": ["1", "2"] },
+                "2": { "type": "TEXT", "value": ["a", null] },
+            })
+        );
+
+        array_bindings(&[vec![Value::I64(1)], vec![]]).expect_err("Should have failed due to uneven rows");
+        array_bindings(&[vec![Value::I64(1)], vec![Value::Float(1.5)]])
+            .expect_err("Should have failed due to mixed types");
+    }
+}
@@ -0,0 +1,205 @@
+use crate::auth::Authenticator;
+use crate::errors::SnowflakeError;
+use crate::DSN_OPTIONS;
+
+use anyhow::anyhow;
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+const CONNECTIONS_VAR: &str = "SNOWFLAKE_CONNECTIONS";
+
+/// Options of a connection besides the DSN options.
+const CREDENTIAL_OPTIONS: &[&str] = &["account", "user", "password"];
+
+/// Option names used by other Snowflake clients, mapped to the DSN options.
+const ALIASES: &[(&str, &str)] = &[
+    ("username", "user"),
+    ("private_key_path", "private_key_file"),
+    ("private_key_passphrase", "private_key_file_pwd"),
+];
+
+/// Loads the options of the connection `name` from `connections.toml`, with any overrides from the environment.
+pub(crate) fn load_connection(name: &str) -> Result<HashMap<String, String>, SnowflakeError> {
+    let contents = match env::var(CONNECTIONS_VAR) {
+        Ok(contents) => contents,
+        Err(_) => {
+            let path = connections_path()?;
+            std::fs::read_to_string(&path).map_err(|e| {
+                SnowflakeError::GeneralError(anyhow!(e).context(format!("Failed to read {}", path.display())))
+            })?
+        }
+    };
+    connection_options(name, &contents, env::vars())
+}
+
+fn connections_path() -> Result<PathBuf, SnowflakeError> {
+    let home = match env::var_os("SNOWFLAKE_HOME") {
+        Some(home) => PathBuf::from(home),
+        None => env::var_os("HOME")
+            .or_else(|| env::var_os("USERPROFILE"))
+            .map(|home| PathBuf::from(home).join(".snowflake"))
+            .ok_or_else(|| {
+                SnowflakeError::GeneralError(anyhow!("Cannot locate connections.toml, home directory is not set"))
+            })?,
+    };
+    Ok(home.join("connections.toml"))
+}
+
+fn connection_options(
+    name: &str,
+    contents: &str,
+    vars: impl Iterator<Item = (String, String)>,
+) -> Result<HashMap<String, String>, SnowflakeError> {
+    let connections: toml::Table = toml::from_str(contents)
+        .map_err(|e| SnowflakeError::GeneralError(anyhow!(e).context("Failed to parse connections.toml")))?;
+
+    let mut options = HashMap::new();
+    if let Some(connection) = connections.get(name) {
+        let table = connection.as_table().ok_or_else(|| {
+            SnowflakeError::GeneralError(anyhow!("Connection '{name}' in connections.toml is not a table"))
+        })?;
+        for (key, value) in table {
+            let value = match value {
+                toml::Value::String(s) => s.to_owned(),
+                value => value.to_string(),
+            };
+            options.insert(option_name(key), value);
+        }
+    }
+
+    let prefix = format!("{CONNECTIONS_VAR}_{}_", name.to_ascii_uppercase());
+    for (var, value) in vars {
+        if let Some(key) = var.strip_prefix(&prefix) {
+            options.insert(option_name(key), value);
+        }
+    }
+
+    if options.is_empty() {
+        return Err(SnowflakeError::GeneralError(anyhow!(
+            "Connection '{name}' not found in connections.toml"
+        )));
+    }
+    Ok(options)
+}
+
+/// Reads the client options from `SNOWFLAKE_{OPTION}` environment variables, failing with every required
+/// variable that is not set.
+pub(crate) fn env_options(
+    vars: impl Iterator<Item = (String, String)>,
+) -> Result<HashMap<String, String>, SnowflakeError> {
+    let options: HashMap<_, _> = vars
+        .filter(|(var, _)| !var.starts_with(CONNECTIONS_VAR))
+        .filter_map(|(var, value)| Some((option_name(var.strip_prefix("SNOWFLAKE_")?), value)))
+        // Other variables, e.g. `SNOWFLAKE_HOME`, don't configure the client.
+        .filter(|(name, _)| CREDENTIAL_OPTIONS.contains(&name.as_str()) || DSN_OPTIONS.contains(&name.as_str()))
+        .filter(|(_, value)| !value.is_empty())
+        .collect();
+
+    let mut required = vec!["account", "user"];
+    match options
+        .get("authenticator")
+        .map(|x| Authenticator::from_str(x))
+        .transpose()?
+    {
+        None | Some(Authenticator::Snowflake | Authenticator::UsernamePasswordMfa) => required.push("password"),
+        Some(Authenticator::SnowflakeJwt) => required.push("private_key_file"),
+        Some(Authenticator::OAuth) => required.push("token"),
+    }
+
+    let missing: Vec<_> = required
+        .into_iter()
+        .filter(|option| !options.contains_key(*option))
+        .map(|option| format!("SNOWFLAKE_{}", option.to_ascii_uppercase()))
+        .collect();
+    if !missing.is_empty() {
+        return Err(SnowflakeError::GeneralError(anyhow!(
+            "Missing environment variables: {}",
+            missing.join(", ")
+        )));
+    }
+    Ok(options)
+}
+
+fn option_name(key: &str) -> String {
+    let key = key.to_ascii_lowercase();
+    ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == key)
+        .map_or(key, |(_, name)| (*name).to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const CONNECTIONS: &str = r#"
+        [prod]
+        account = "xy12345.us-east-1"
+        user = "etl"
+        authenticator = "SNOWFLAKE_JWT"
+        private_key_path = "/keys/etl.p8"
+        port = 443
+
+        [dev]
+        account = "xy12345"
+    "#;
+
+    #[test]
+    fn test_connection_options() {
+        let vars = vec![
+            ("SNOWFLAKE_CONNECTIONS_PROD_ROLE".to_owned(), "LOADER".to_owned()),
+            ("SNOWFLAKE_CONNECTIONS_PROD_USER".to_owned(), "admin".to_owned()),
+            ("SNOWFLAKE_CONNECTIONS_DEV_ROLE".to_owned(), "DEV".to_owned()),
+        ];
+        let options = connection_options("prod", CONNECTIONS, vars.into_iter()).unwrap();
+
+        assert_eq!(options["account"], "xy12345.us-east-1");
+        assert_eq!(options["user"], "admin");
+        assert_eq!(options["role"], "LOADER");
+        assert_eq!(options["private_key_file"], "/keys/etl.p8");
+        assert_eq!(options["port"], "443");
+        assert_eq!(options.len(), 6);
+
+        assert!(connection_options("staging", CONNECTIONS, std::iter::empty()).is_err());
+    }
+
+    fn vars(vars: &[(&str, &str)]) -> impl Iterator<Item = (String, String)> {
+        vars.iter()
+            .map(|(var, value)| (var.to_string(), value.to_string()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_env_options() {
+        let options = env_options(vars(&[
+            ("SNOWFLAKE_ACCOUNT", "xy12345"),
+            ("SNOWFLAKE_USER", "etl"),
+            ("SNOWFLAKE_AUTHENTICATOR", "snowflake_jwt"),
+            ("SNOWFLAKE_PRIVATE_KEY_PATH", "/keys/etl.p8"),
+            ("SNOWFLAKE_WAREHOUSE", "LOAD_WH"),
+            ("SNOWFLAKE_CONNECTIONS_PROD_ROLE", "LOADER"),
+            ("SNOWFLAKE_HOME", "/root/.snowflake"),
+            ("HOME", "/root"),
+        ]))
+        .unwrap();
+        assert_eq!(options["private_key_file"], "/keys/etl.p8");
+        assert_eq!(options["warehouse"], "LOAD_WH");
+        assert_eq!(options.len(), 5);
+
+        let err = env_options(vars(&[("SNOWFLAKE_USER", "etl"), ("SNOWFLAKE_PASSWORD", "")])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Snowflake error: Missing environment variables: SNOWFLAKE_ACCOUNT, SNOWFLAKE_PASSWORD"
+        );
+
+        let err = env_options(vars(&[("SNOWFLAKE_AUTHENTICATOR", "oauth")])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Snowflake error: Missing environment variables: SNOWFLAKE_ACCOUNT, SNOWFLAKE_USER, SNOWFLAKE_TOKEN"
+        );
+    }
+}
@@ -15,11 +15,23 @@ pub enum SnowflakeError {
     DeserializationError(anyhow::Error, Option<DeserializationErrorContext>),
     #[error("Snowflake execution error: {0}")]
     ExecutionError(anyhow::Error, Option<ErrorResult>),
+    #[error("Snowflake session error: {0}")]
+    SessionError(anyhow::Error, Option<String>),
+    #[error("Snowflake transaction error: {0}")]
+    TransactionError(anyhow::Error),
     #[error("Snowflake error: {0}")]
     GeneralError(anyhow::Error),
 }
 
 impl SnowflakeError {
+    /// Returns the known cause of an authentication error, if any.
+    pub fn authentication_failure(&self) -> Option<&AuthenticationFailure> {
+        match self {
+            Self::AuthenticationError(e) => e.downcast_ref(),
+            _ => None,
+        }
+    }
+
     #[allow(unused)]
     pub(crate) fn new_deserialization_error(err: anyhow::Error) -> Self {
         Self::DeserializationError(err, None)
@@ -61,6 +73,27 @@ impl SnowflakeError {
     }
 }
 
+/// Known causes of a [`SnowflakeError::AuthenticationError`].
+///
+/// Can be retrieved with [`SnowflakeError::authentication_failure`].
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum AuthenticationFailure {
+    #[error("OAuth access token expired: {0}")]
+    OAuthTokenExpired(String),
+    #[error("Invalid OAuth access token: {0}")]
+    OAuthTokenInvalid(String),
+}
+
+impl AuthenticationFailure {
+    pub(crate) fn from_code(code: Option<&str>, message: &str) -> Option<Self> {
+        match code? {
+            "390318" => Some(Self::OAuthTokenExpired(message.to_owned())),
+            "390303" => Some(Self::OAuthTokenInvalid(message.to_owned())),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct DeserializationErrorContext {
     pub field: Option<String>,
@@ -83,3 +116,38 @@ impl std::error::Error for WrappedDecimalConvertError {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authentication_failure() {
+        let err = SnowflakeError::AuthenticationError(
+            AuthenticationFailure::from_code(Some("390318"), "OAuth access token expired.")
+                .unwrap()
+                .into(),
+        );
+        assert_eq!(
+            err.authentication_failure(),
+            Some(&AuthenticationFailure::OAuthTokenExpired(
+                "OAuth access token expired.".to_owned()
+            ))
+        );
+
+        assert_eq!(
+            AuthenticationFailure::from_code(Some("390303"), "Invalid OAuth access token."),
+            Some(AuthenticationFailure::OAuthTokenInvalid(
+                "Invalid OAuth access token.".to_owned()
+            ))
+        );
+        assert_eq!(
+            AuthenticationFailure::from_code(Some("390100"), "Incorrect username"),
+            None
+        );
+        assert_eq!(AuthenticationFailure::from_code(None, "Unknown"), None);
+
+        let err = SnowflakeError::AuthenticationError(anyhow::anyhow!("Unknown"));
+        assert_eq!(err.authentication_failure(), None);
+    }
+}
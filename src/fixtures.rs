@@ -0,0 +1,416 @@
+//! Recording of HTTP exchanges with Snowflake into fixtures, and their replay without network access.
+//!
+//! Both run a local server the session sends its requests to. When recording, requests are forwarded to
+//! Snowflake and every exchange is written to the fixture directory with secrets redacted. Chunk URLs are
+//! rewritten to go through the server as well, so chunk downloads are recorded too.
+
+use crate::errors::SnowflakeError;
+use crate::mock::{json_response, not_found, serve};
+use crate::utils::https_proxy;
+
+use hyper::header::{CONTENT_ENCODING, CONTENT_TYPE};
+use hyper::{Body, Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+const REDACTED: &str = "<redacted>";
+
+/// Fields holding secrets, replaced in recorded fixtures.
+const SECRET_FIELDS: &[&str] = &[
+    "PASSWORD",
+    "PASSCODE",
+    "TOKEN",
+    "token",
+    "masterToken",
+    "sessionToken",
+    "oldSessionToken",
+    "mfaToken",
+    "idToken",
+    "qrmk",
+    // Stage credentials and keys of PUT and GET commands.
+    "AWS_KEY_ID",
+    "AWS_SECRET_KEY",
+    "AWS_TOKEN",
+    "AZURE_SAS_TOKEN",
+    "GCS_ACCESS_TOKEN",
+    "presignedUrl",
+    "queryStageMasterKey",
+];
+
+/// Headers forwarded from the session to Snowflake, in addition to any `x-amz-` header of chunk downloads.
+const FORWARDED_HEADERS: &[&str] = &["accept", "authorization", "content-type", "user-agent"];
+
+#[derive(Clone, Debug)]
+pub(crate) enum FixtureMode {
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+/// Single request and its response, as stored in the fixture directory.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Exchange {
+    method: String,
+    path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sql_text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    request: Option<serde_json::Value>,
+    status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_encoding: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    json: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body_base64: Option<String>,
+}
+
+impl Exchange {
+    /// Requests are matched on method, path and SQL text, as ids in query strings differ between runs.
+    fn key(&self) -> (String, String, Option<String>) {
+        (self.method.clone(), self.path.clone(), self.sql_text.clone())
+    }
+
+    fn to_response(&self, url: &str) -> Response<Body> {
+        let mut res = match (&self.json, &self.body_base64) {
+            (Some(json), _) => json_response(with_chunk_urls(json.clone(), url)),
+            (None, Some(body)) => Response::new(Body::from(base64::decode(body).unwrap_or_default())),
+            (None, None) => Response::new(Body::empty()),
+        };
+        *res.status_mut() = StatusCode::from_u16(self.status).unwrap_or(StatusCode::OK);
+        if let Some(encoding) = self.content_encoding.as_ref().and_then(|x| x.parse().ok()) {
+            res.headers_mut().insert(CONTENT_ENCODING, encoding);
+        }
+        res
+    }
+}
+
+#[derive(Debug)]
+struct RecordState {
+    dir: PathBuf,
+    upstream: String,
+    client: reqwest::Client,
+    sequence: usize,
+    chunks: Vec<String>,
+}
+
+type ReplayState = HashMap<(String, String, Option<String>), VecDeque<Exchange>>;
+
+/// Local server recording or replaying fixtures, stopped once dropped.
+#[derive(Debug)]
+pub(crate) struct FixtureServer {
+    url: String,
+    server: JoinHandle<()>,
+}
+
+impl FixtureServer {
+    /// Starts recording requests forwarded to `upstream` through `proxy`, or replaying the fixtures recorded
+    /// earlier.
+    pub(crate) async fn start(
+        mode: &FixtureMode,
+        upstream: &str,
+        proxy: Option<&str>,
+        timeout: Option<Duration>,
+    ) -> Result<Self, SnowflakeError> {
+        let (url, server) = match mode {
+            FixtureMode::Record(dir) => {
+                std::fs::create_dir_all(dir).map_err(|e| SnowflakeError::GeneralError(e.into()))?;
+                let mut builder = reqwest::Client::builder().gzip(false).deflate(false);
+                if let Some(proxy) = proxy {
+                    builder = builder.proxy(https_proxy(proxy)?);
+                }
+                if let Some(timeout) = timeout {
+                    builder = builder.timeout(timeout);
+                }
+                let client = builder.build().map_err(|e| SnowflakeError::GeneralError(e.into()))?;
+                let state = Arc::new(Mutex::new(RecordState {
+                    dir: dir.clone(),
+                    upstream: upstream.to_owned(),
+                    client,
+                    sequence: 0,
+                    chunks: vec![],
+                }));
+                serve(move |url, req| record(state.clone(), url, req)).await?
+            }
+            FixtureMode::Replay(dir) => {
+                let state = Arc::new(Mutex::new(load_fixtures(dir)?));
+                serve(move |url, req| replay(state.clone(), url, req)).await?
+            }
+        };
+        Ok(FixtureServer { url, server })
+    }
+
+    pub(crate) fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl Drop for FixtureServer {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+fn load_fixtures(dir: &Path) -> Result<ReplayState, SnowflakeError> {
+    let mut paths = std::fs::read_dir(dir)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|e| e.path()))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| SnowflakeError::GeneralError(e.into()))?;
+    paths.retain(|path| path.extension().is_some_and(|ext| ext == "json"));
+    paths.sort();
+
+    let mut state = ReplayState::new();
+    for path in paths {
+        let text = std::fs::read_to_string(&path).map_err(|e| SnowflakeError::GeneralError(e.into()))?;
+        let exchange: Exchange = serde_json::from_str(&text)
+            .map_err(|e| SnowflakeError::new_deserialization_error_with_value(e.into(), path.display().to_string()))?;
+        state.entry(exchange.key()).or_default().push_back(exchange);
+    }
+    Ok(state)
+}
+
+async fn replay(state: Arc<Mutex<ReplayState>>, url: String, req: Request<Body>) -> Response<Body> {
+    let method = req.method().to_string();
+    let path = req.uri().path().to_owned();
+    let sql_text = hyper::body::to_bytes(req.into_body())
+        .await
+        .ok()
+        .and_then(|body| sql_text(&body));
+
+    let mut state = state.lock().unwrap();
+    // Exchanges are replayed in the order they were recorded, repeating the last one, e.g. for polling.
+    match state.get_mut(&(method, path, sql_text)) {
+        Some(exchanges) if exchanges.len() > 1 => exchanges.pop_front().unwrap().to_response(&url),
+        Some(exchanges) => exchanges[0].to_response(&url),
+        None => not_found(),
+    }
+}
+
+async fn record(state: Arc<Mutex<RecordState>>, url: String, req: Request<Body>) -> Response<Body> {
+    match forward(&state, req).await {
+        Ok((exchange, res)) => {
+            let mut state = state.lock().unwrap();
+            state.sequence += 1;
+            let path = state.dir.join(format!("{:04}.json", state.sequence));
+            let written = serde_json::to_string_pretty(&exchange)
+                .map_err(anyhow::Error::from)
+                .and_then(|text| std::fs::write(&path, text).map_err(anyhow::Error::from));
+            if let Err(e) = written {
+                log::error!("Failed to write fixture {}: {e}", path.display());
+            }
+            match res {
+                Some(json) => {
+                    let mut res = exchange.to_response(&url);
+                    *res.body_mut() = Body::from(with_chunk_urls(json, &url).to_string());
+                    res
+                }
+                None => exchange.to_response(&url),
+            }
+        }
+        Err(e) => {
+            log::error!("Failed to forward request: {e}");
+            let mut res = Response::new(Body::from(e.to_string()));
+            *res.status_mut() = StatusCode::BAD_GATEWAY;
+            res
+        }
+    }
+}
+
+/// Forwards a request to Snowflake, returning the exchange to record together with the unredacted JSON
+/// response for the session.
+async fn forward(
+    state: &Mutex<RecordState>,
+    req: Request<Body>,
+) -> Result<(Exchange, Option<serde_json::Value>), anyhow::Error> {
+    let method = req.method().clone();
+    let path = req.uri().path().to_owned();
+    let path_and_query = req.uri().path_and_query().map_or(path.clone(), |x| x.to_string());
+    let headers = req.headers().clone();
+    let body = hyper::body::to_bytes(req.into_body()).await?;
+
+    let (client, url) = {
+        let state = state.lock().unwrap();
+        let url = match path.strip_prefix("/chunks/") {
+            Some(index) => state
+                .chunks
+                .get(index.parse::<usize>()?)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("Unknown chunk {index}"))?,
+            None => format!("{}{path_and_query}", state.upstream),
+        };
+        (state.client.clone(), url)
+    };
+
+    let mut upstream = client
+        .request(reqwest::Method::from_bytes(method.as_str().as_bytes())?, &url)
+        .body(body.to_vec());
+    for (name, value) in headers.iter() {
+        if FORWARDED_HEADERS.contains(&name.as_str()) || name.as_str().starts_with("x-amz-") {
+            upstream = upstream.header(name.as_str(), value.as_bytes());
+        }
+    }
+    let res = upstream.send().await?;
+
+    let status = res.status().as_u16();
+    let content_encoding = res
+        .headers()
+        .get(CONTENT_ENCODING)
+        .and_then(|x| x.to_str().ok())
+        .map(str::to_owned);
+    let is_json = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|x| x.to_str().ok())
+        .is_some_and(|x| x.contains("json"));
+    let bytes = res.bytes().await?;
+
+    let json = match (is_json, &content_encoding) {
+        (true, None) => serde_json::from_slice::<serde_json::Value>(&bytes).ok(),
+        _ => None,
+    };
+    let json = json.map(|json| rewrite_chunk_urls(state, json));
+
+    let exchange = Exchange {
+        method: method.to_string(),
+        path,
+        sql_text: sql_text(&body),
+        request: serde_json::from_slice(&body).ok().map(redact),
+        status,
+        content_encoding,
+        json: json.clone().map(redact),
+        body_base64: json.is_none().then(|| base64::encode(&bytes)),
+    };
+    Ok((exchange, json))
+}
+
+fn sql_text(body: &[u8]) -> Option<String> {
+    serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|body| body["sqlText"].as_str().map(str::to_owned))
+}
+
+/// Replaces chunk URLs by paths on the fixture server, keeping the original URLs to forward downloads to.
+fn rewrite_chunk_urls(state: &Mutex<RecordState>, mut json: serde_json::Value) -> serde_json::Value {
+    if let Some(chunks) = json["data"]["chunks"].as_array_mut() {
+        let mut state = state.lock().unwrap();
+        for chunk in chunks {
+            if let Some(url) = chunk["url"].as_str() {
+                state.chunks.push(url.to_owned());
+                chunk["url"] = serde_json::json!(format!("/chunks/{}", state.chunks.len() - 1));
+            }
+        }
+    }
+    json
+}
+
+/// Prefixes chunk paths with the URL of the fixture server.
+fn with_chunk_urls(mut json: serde_json::Value, url: &str) -> serde_json::Value {
+    if let Some(chunks) = json["data"]["chunks"].as_array_mut() {
+        for chunk in chunks {
+            if let Some(path) = chunk["url"].as_str().filter(|path| path.starts_with('/')) {
+                chunk["url"] = serde_json::json!(format!("{url}{path}"));
+            }
+        }
+    }
+    json
+}
+
+fn redact(mut json: serde_json::Value) -> serde_json::Value {
+    match &mut json {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if value.is_null() {
+                    continue;
+                }
+                if SECRET_FIELDS.contains(&key.as_str()) {
+                    *value = serde_json::json!(REDACTED);
+                }
+                // Chunk headers hold the key chunks are encrypted with.
+                else if key == "chunkHeaders" {
+                    if let Some(headers) = value.as_object_mut() {
+                        headers
+                            .values_mut()
+                            .for_each(|header| *header = serde_json::json!(REDACTED));
+                    }
+                }
+                else {
+                    *value = redact(value.take());
+                }
+            }
+        }
+        serde_json::Value::Array(values) => {
+            for value in values.iter_mut() {
+                *value = redact(value.take());
+            }
+        }
+        _ => {}
+    }
+    json
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_redact() {
+        let login = json!({
+            "data": {
+                "LOGIN_NAME": "user",
+                "PASSWORD": "secret",
+                "PASSCODE": null,
+                "SESSION_PARAMETERS": { "TIMEZONE": "Etc/GMT" },
+            }
+        });
+        assert_eq!(
+            redact(login),
+            json!({
+                "data": {
+                    "LOGIN_NAME": "user",
+                    "PASSWORD": REDACTED,
+                    "PASSCODE": null,
+                    "SESSION_PARAMETERS": { "TIMEZONE": "Etc/GMT" },
+                }
+            })
+        );
+
+        let result = json!({
+            "data": {
+                "token": "a",
+                "qrmk": "b",
+                "chunkHeaders": { "x-amz-server-side-encryption-customer-key": "c" },
+                "chunks": [{ "url": "/chunks/0", "rowCount": 1 }],
+                "rowset": [["1", "token"]],
+            }
+        });
+        assert_eq!(
+            redact(result),
+            json!({
+                "data": {
+                    "token": REDACTED,
+                    "qrmk": REDACTED,
+                    "chunkHeaders": { "x-amz-server-side-encryption-customer-key": REDACTED },
+                    "chunks": [{ "url": "/chunks/0", "rowCount": 1 }],
+                    "rowset": [["1", "token"]],
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_with_chunk_urls() {
+        let json = json!({ "data": { "chunks": [{ "url": "/chunks/0" }, { "url": "https://s3/chunk" }] } });
+        assert_eq!(
+            with_chunk_urls(json, "http://127.0.0.1:1"),
+            json!({ "data": { "chunks": [{ "url": "http://127.0.0.1:1/chunks/0" }, { "url": "https://s3/chunk" }] } })
+        );
+    }
+}
@@ -0,0 +1,487 @@
+//! Local server emulating the parts of the Snowflake API used by this crate, so tests can run without an account.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), snowstorm::errors::SnowflakeError> {
+//! use snowstorm::mock::{MockColumn, MockResult, MockServer};
+//! use snowstorm::responses::result::vec::VecResult;
+//!
+//! let server = MockServer::start().await?;
+//! server.on_query(
+//!     "SELECT id FROM users",
+//!     MockResult::new(vec![MockColumn::fixed("ID")]).row(&[Some("1")]),
+//! );
+//!
+//! let session = server.snowstorm().connect().await?;
+//! let res = session.execute::<VecResult>("SELECT id FROM users").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::errors::SnowflakeError;
+use crate::Snowstorm;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{header::CONTENT_TYPE, Body, Method, Request, Response, Server, StatusCode};
+use serde_json::json;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+/// Column of a [`MockResult`], always nullable.
+#[derive(Clone, Debug)]
+pub struct MockColumn {
+    name: String,
+    data_type: &'static str,
+}
+
+impl MockColumn {
+    /// `NUMBER(18, 0)` column.
+    pub fn fixed(name: &str) -> Self {
+        Self::new(name, "fixed")
+    }
+
+    pub fn real(name: &str) -> Self {
+        Self::new(name, "real")
+    }
+
+    pub fn text(name: &str) -> Self {
+        Self::new(name, "text")
+    }
+
+    pub fn boolean(name: &str) -> Self {
+        Self::new(name, "boolean")
+    }
+
+    fn new(name: &str, data_type: &'static str) -> Self {
+        Self {
+            name: name.to_owned(),
+            data_type,
+        }
+    }
+
+    fn accepts(&self, value: &str) -> bool {
+        match self.data_type {
+            "fixed" => value.parse::<i64>().is_ok(),
+            "real" => value.parse::<f64>().is_ok(),
+            "boolean" => value.parse::<bool>().is_ok(),
+            _ => true,
+        }
+    }
+
+    fn row_type(&self) -> serde_json::Value {
+        let fixed = self.data_type == "fixed";
+        json!({
+            "name": self.name,
+            "type": self.data_type,
+            "nullable": true,
+            "precision": fixed.then_some(18),
+            "scale": fixed.then_some(0),
+            "byteLength": null,
+            "extTypeName": null,
+        })
+    }
+}
+
+/// Canned result returned by a [`MockServer`] for a query.
+#[derive(Clone, Debug)]
+pub struct MockResult {
+    columns: Vec<MockColumn>,
+    rows: Vec<Vec<Option<String>>>,
+    chunk_size: Option<usize>,
+    arrow: bool,
+    statement_type_id: i64,
+    stats: Option<serde_json::Value>,
+    stage_info: Option<serde_json::Value>,
+    error: Option<(String, String)>,
+}
+
+impl MockResult {
+    pub fn new(columns: Vec<MockColumn>) -> Self {
+        Self {
+            columns,
+            rows: vec![],
+            chunk_size: None,
+            arrow: false,
+            statement_type_id: 0x1000,
+            stats: None,
+            stage_info: None,
+            error: None,
+        }
+    }
+
+    /// Result of an `INSERT` statement.
+    pub fn inserted(rows: u64) -> Self {
+        let mut res = Self::new(vec![MockColumn::fixed("number of rows inserted")]).row(&[Some(&rows.to_string())]);
+        res.statement_type_id = 0x3100;
+        res.stats = Some(json!({ "numRowsInserted": rows }));
+        res
+    }
+
+    /// Failed query, with a Snowflake error code such as `002003`.
+    pub fn error(code: &str, message: &str) -> Self {
+        let mut res = Self::new(vec![]);
+        res.error = Some((code.to_owned(), message.to_owned()));
+        res
+    }
+
+    /// Adds a row with values as Snowflake returns them in JSON results.
+    ///
+    /// Panics if the row doesn't match the columns.
+    pub fn row(mut self, values: &[Option<&str>]) -> Self {
+        assert_eq!(
+            values.len(),
+            self.columns.len(),
+            "Row should have a value for every column"
+        );
+        for (column, value) in self.columns.iter().zip(values) {
+            if let Some(value) = value {
+                assert!(column.accepts(value), "Invalid {} value {value}", column.data_type);
+            }
+        }
+        self.rows.push(values.iter().map(|v| v.map(str::to_owned)).collect());
+        self
+    }
+
+    /// Reports the type of the statement, e.g. `0x5000` for transaction control or `0x6000` for DDL statements.
+    pub fn statement_type_id(mut self, id: i64) -> Self {
+        self.statement_type_id = id;
+        self
+    }
+
+    /// Returns stage information with the result, as Snowflake does for `PUT` and `GET` commands.
+    pub fn stage_info(mut self, stage_info: serde_json::Value) -> Self {
+        self.stage_info = Some(stage_info);
+        self
+    }
+
+    /// Returns the first `rows` rows inline and serves the others as chunks of `rows` rows each.
+    pub fn chunk_size(mut self, rows: usize) -> Self {
+        self.chunk_size = Some(rows.max(1));
+        self
+    }
+
+    /// Returns the rows in Arrow format instead of JSON.
+    #[cfg(feature = "arrow")]
+    pub fn arrow(mut self) -> Self {
+        self.arrow = true;
+        self
+    }
+
+    fn default_result() -> Self {
+        let mut res = Self::new(vec![MockColumn::text("status")]).row(&[Some("Statement executed successfully.")]);
+        res.statement_type_id = 0x4000;
+        res
+    }
+}
+
+/// Query request received by a [`MockServer`].
+#[derive(Clone, Debug)]
+pub struct MockRequest {
+    pub sql_text: String,
+    pub async_exec: bool,
+    pub parameters: serde_json::Value,
+    pub bindings: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    results: HashMap<String, MockResult>,
+    queries: HashMap<String, MockResult>,
+    chunks: HashMap<String, Vec<u8>>,
+    requests: Vec<MockRequest>,
+}
+
+/// Local HTTP server answering login, query, monitoring and chunk requests with canned results.
+///
+/// Queries without a result registered with [`MockServer::on_query`] succeed with a status message.
+/// The server stops once dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    url: String,
+    state: Arc<Mutex<MockState>>,
+    server: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Starts a server on a free local port.
+    pub async fn start() -> Result<Self, SnowflakeError> {
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let service_state = state.clone();
+        let (url, server) = serve(move |url, req| handle(service_state.clone(), url, req)).await?;
+        Ok(MockServer { url, state, server })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Client connecting to this server.
+    pub fn snowstorm(&self) -> Snowstorm {
+        Snowstorm::new("mock".to_owned(), "mock".to_owned(), "mock".to_owned()).base_url(&self.url)
+    }
+
+    /// Returns `result` for every query with the exact text `sql`, ignoring surrounding whitespace.
+    pub fn on_query(&self, sql: &str, result: MockResult) {
+        self.state.lock().unwrap().queries.insert(sql.trim().to_owned(), result);
+    }
+
+    /// Query requests received so far, in order.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// Serves requests on a free local port, passing the URL of the server to `handler` with every request.
+pub(crate) async fn serve<H, F>(handler: H) -> Result<(String, JoinHandle<()>), SnowflakeError>
+where
+    H: Fn(String, Request<Body>) -> F + Clone + Send + Sync + 'static,
+    F: Future<Output = Response<Body>> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").map_err(|e| SnowflakeError::GeneralError(e.into()))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| SnowflakeError::GeneralError(e.into()))?;
+    let addr = listener
+        .local_addr()
+        .map_err(|e| SnowflakeError::GeneralError(e.into()))?;
+    let url = format!("http://{addr}");
+
+    let service_url = url.clone();
+    let make_service = make_service_fn(move |_| {
+        let handler = handler.clone();
+        let url = service_url.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let res = handler(url.clone(), req);
+                async move { Ok::<_, Infallible>(res.await) }
+            }))
+        }
+    });
+
+    let server = Server::from_tcp(listener)
+        .map_err(|e| SnowflakeError::GeneralError(e.into()))?
+        .serve(make_service);
+    let server = tokio::spawn(async move {
+        if let Err(e) = server.await {
+            log::error!("Local server failed: {e}");
+        }
+    });
+
+    Ok((url, server))
+}
+
+async fn handle(state: Arc<Mutex<MockState>>, url: String, req: Request<Body>) -> Response<Body> {
+    let method = req.method().clone();
+    let path = req.uri().path().to_owned();
+    let body = hyper::body::to_bytes(req.into_body()).await.unwrap_or_default();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap_or_default();
+
+    let mut state = state.lock().unwrap();
+    let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+    match (method, segments.as_slice()) {
+        (Method::POST, ["session", "v1", "login-request"]) => success(json!({
+            "token": "mock-session-token",
+            "masterToken": "mock-master-token",
+            "validityInSeconds": 3600,
+            "masterValidityInSeconds": 14400,
+            "sessionInfo": { "roleName": "PUBLIC" },
+            "parameters": [{ "name": "TIMEZONE", "value": "Etc/GMT" }],
+        })),
+        (Method::POST, ["session", "token-request"]) => success(json!({
+            "sessionToken": "mock-session-token",
+            "validityInSecondsST": 3600,
+        })),
+        (Method::POST, ["session"] | ["session", "heartbeat"] | ["queries", "v1", "abort-request"]) => {
+            success(serde_json::Value::Null)
+        }
+        (Method::POST, ["queries", "v1", "query-request"]) => state.query_request(&url, &body),
+        (Method::GET, ["queries", query_id, "result"]) => match state.results.get(*query_id).cloned() {
+            Some(result) => state.result(&url, query_id, &result),
+            None => not_found(),
+        },
+        (Method::GET, ["monitoring", "queries", query_id]) => state.monitoring(query_id),
+        (Method::GET, ["chunks", ..]) => match state.chunks.get(&path) {
+            Some(chunk) => Response::new(Body::from(chunk.clone())),
+            None => not_found(),
+        },
+        _ => not_found(),
+    }
+}
+
+impl MockState {
+    fn query_request(&mut self, url: &str, body: &serde_json::Value) -> Response<Body> {
+        let request = MockRequest {
+            sql_text: body["sqlText"].as_str().unwrap_or_default().to_owned(),
+            async_exec: body["asyncExec"].as_bool().unwrap_or_default(),
+            parameters: body["parameters"].clone(),
+            bindings: body.get("bindings").cloned(),
+        };
+        let result = self
+            .queries
+            .get(request.sql_text.trim())
+            .cloned()
+            .unwrap_or_else(MockResult::default_result);
+        let query_id = uuid::Uuid::new_v4().to_string();
+        self.results.insert(query_id.clone(), result.clone());
+
+        let res = match request.async_exec {
+            true => success(json!({
+                "queryId": query_id,
+                "getResultUrl": format!("/queries/{query_id}/result"),
+                "queryAbortsAfterSecs": 300,
+                "progressDesc": null,
+            })),
+            false => self.result(url, &query_id, &result),
+        };
+        self.requests.push(request);
+        res
+    }
+
+    fn result(&mut self, url: &str, query_id: &str, result: &MockResult) -> Response<Body> {
+        if let Some((code, message)) = &result.error {
+            return json_response(json!({
+                "success": false,
+                "code": code,
+                "message": message,
+                "data": { "errorCode": code, "internalError": false, "queryId": query_id },
+            }));
+        }
+
+        let chunk_size = result.chunk_size.unwrap_or(result.rows.len()).max(1);
+        let mut row_chunks = result.rows.chunks(chunk_size);
+        let first = row_chunks.next().unwrap_or_default();
+
+        let mut chunks = vec![];
+        for (index, rows) in row_chunks.enumerate() {
+            let path = format!("/chunks/{query_id}/{index}");
+            let body = match result.arrow {
+                true => arrow_stream(&result.columns, rows),
+                // Like Snowflake, JSON chunks don't enclose their rows in brackets.
+                false => rows
+                    .iter()
+                    .map(|row| json!(row).to_string())
+                    .collect::<Vec<_>>()
+                    .join(",\n")
+                    .into_bytes(),
+            };
+            self.chunks.insert(path.clone(), body);
+            chunks.push(json!({ "rowCount": rows.len(), "url": format!("{url}{path}") }));
+        }
+
+        let mut data = json!({
+            "rowtype": result.columns.iter().map(MockColumn::row_type).collect::<Vec<_>>(),
+            "queryId": query_id,
+            "total": result.rows.len(),
+            "chunks": chunks,
+            "qrmk": "mock",
+            "queryResultFormat": if result.arrow { "arrow" } else { "json" },
+            "statementTypeId": result.statement_type_id,
+            "stats": result.stats,
+            "stageInfo": result.stage_info,
+            "finalRoleName": "PUBLIC",
+        });
+        match result.arrow {
+            true => data["rowsetBase64"] = json!(base64::encode(arrow_stream(&result.columns, first))),
+            false => data["rowset"] = json!(first),
+        }
+        success(data)
+    }
+
+    fn monitoring(&self, query_id: &str) -> Response<Body> {
+        let queries = match self.results.get(query_id) {
+            Some(result) => {
+                let (status, code, message) = match &result.error {
+                    Some((code, message)) => ("FAILED_WITH_ERROR", Some(code), Some(message)),
+                    None => ("SUCCESS", None, None),
+                };
+                vec![json!({
+                    "id": query_id,
+                    "status": status,
+                    "errorCode": code,
+                    "errorMessage": message,
+                    "internal": false,
+                })]
+            }
+            None => vec![],
+        };
+        success(json!({ "queries": queries }))
+    }
+}
+
+#[cfg(feature = "arrow")]
+fn arrow_stream(columns: &[MockColumn], rows: &[Vec<Option<String>>]) -> Vec<u8> {
+    use arrow2::array::{Array, BooleanArray, Float64Array, Int64Array, Utf8Array};
+    use arrow2::chunk::Chunk;
+    use arrow2::datatypes::{DataType, Field, Metadata, Schema};
+    use arrow2::io::ipc::write::{StreamWriter, WriteOptions};
+
+    let mut fields = vec![];
+    let mut arrays: Vec<Box<dyn Array>> = vec![];
+    for (i, column) in columns.iter().enumerate() {
+        let values: Vec<Option<&str>> = rows.iter().map(|row| row[i].as_deref()).collect();
+        // Values are validated when the row is added.
+        let (data_type, array) = match column.data_type {
+            "fixed" => (
+                DataType::Int64,
+                Int64Array::from(values.iter().map(|v| v.map(|x| x.parse().unwrap())).collect::<Vec<_>>()).boxed(),
+            ),
+            "real" => (
+                DataType::Float64,
+                Float64Array::from(values.iter().map(|v| v.map(|x| x.parse().unwrap())).collect::<Vec<_>>()).boxed(),
+            ),
+            "boolean" => (
+                DataType::Boolean,
+                BooleanArray::from(values.iter().map(|v| v.map(|x| x.parse().unwrap())).collect::<Vec<_>>()).boxed(),
+            ),
+            _ => (DataType::Utf8, Utf8Array::<i32>::from(values).boxed()),
+        };
+
+        let mut metadata = Metadata::from([("logicalType".to_owned(), column.data_type.to_ascii_uppercase())]);
+        if column.data_type == "fixed" {
+            metadata.insert("precision".to_owned(), "18".to_owned());
+            metadata.insert("scale".to_owned(), "0".to_owned());
+        }
+        fields.push(Field::new(&column.name, data_type, true).with_metadata(metadata));
+        arrays.push(array);
+    }
+
+    let mut stream = vec![];
+    let mut writer = StreamWriter::new(&mut stream, WriteOptions { compression: None });
+    writer
+        .start(&Schema::from(fields), None)
+        .and_then(|_| writer.write(&Chunk::new(arrays), None))
+        .and_then(|_| writer.finish())
+        .expect("Arrow stream should be written to memory");
+    stream
+}
+
+#[cfg(not(feature = "arrow"))]
+fn arrow_stream(_columns: &[MockColumn], _rows: &[Vec<Option<String>>]) -> Vec<u8> {
+    unreachable!("Arrow results require the arrow feature")
+}
+
+pub(crate) fn success(data: serde_json::Value) -> Response<Body> {
+    json_response(json!({ "success": true, "code": null, "message": null, "data": data }))
+}
+
+pub(crate) fn json_response(body: serde_json::Value) -> Response<Body> {
+    let mut res = Response::new(Body::from(body.to_string()));
+    res.headers_mut()
+        .insert(CONTENT_TYPE, "application/json".parse().unwrap());
+    res
+}
+
+pub(crate) fn not_found() -> Response<Body> {
+    let mut res = Response::new(Body::empty());
+    *res.status_mut() = StatusCode::NOT_FOUND;
+    res
+}
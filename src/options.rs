@@ -0,0 +1,100 @@
+use crate::bindings::{Binding, Params};
+
+use serde_json::json;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Options for executing a single query, passed to [`Session::execute_with_options`].
+///
+/// Parameters set here override the session parameters for this query only.
+///
+/// [`Session::execute_with_options`]: crate::session::Session::execute_with_options
+#[derive(Clone, Debug, Default)]
+pub struct ExecuteOptions {
+    pub(crate) bindings: Option<HashMap<String, Binding>>,
+    pub(crate) parameters: HashMap<String, serde_json::Value>,
+    pub(crate) multi_statement_count: Option<u32>,
+    pub(crate) async_exec: bool,
+}
+
+impl ExecuteOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds values to the `?`, `:1` or `:name` placeholders of the query.
+    pub fn params(mut self, params: impl Into<Params>) -> Self {
+        self.bindings = Some(params.into().to_bindings());
+        self
+    }
+
+    /// Sets a parameter for this query, e.g. `ROWS_PER_RESULTSET`.
+    pub fn parameter(mut self, name: &str, value: impl Into<serde_json::Value>) -> Self {
+        self.parameters.insert(name.to_ascii_uppercase(), value.into());
+        self
+    }
+
+    /// Tags the query with `QUERY_TAG`, shown in the query history.
+    pub fn query_tag(self, tag: &str) -> Self {
+        self.parameter("QUERY_TAG", tag)
+    }
+
+    /// Cancels the query on the server once it runs longer than `timeout`, using `STATEMENT_TIMEOUT_IN_SECONDS`.
+    pub fn statement_timeout(self, timeout: Duration) -> Self {
+        self.parameter("STATEMENT_TIMEOUT_IN_SECONDS", timeout.as_secs())
+    }
+
+    pub(crate) fn bindings(mut self, bindings: HashMap<String, Binding>) -> Self {
+        self.bindings = Some(bindings);
+        self
+    }
+
+    /// Allows up to `count` statements in the query, where 0 allows any number of statements.
+    pub(crate) fn multi_statement_count(mut self, count: u32) -> Self {
+        self.multi_statement_count = Some(count);
+        self
+    }
+
+    pub(crate) fn async_exec(mut self) -> Self {
+        self.async_exec = true;
+        self
+    }
+
+    /// Builds the parameters sent with the query request.
+    pub(crate) fn to_parameters(&self, result_format: &str) -> serde_json::Value {
+        let mut parameters = json!({ "PYTHON_CONNECTOR_QUERY_RESULT_FORMAT": result_format });
+        for (name, value) in &self.parameters {
+            parameters[name] = value.clone();
+        }
+        if let Some(count) = self.multi_statement_count {
+            parameters["MULTI_STATEMENT_COUNT"] = json!(count);
+        }
+        parameters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_to_parameters() {
+        let options = ExecuteOptions::new()
+            .query_tag("etl")
+            .statement_timeout(Duration::from_secs(90))
+            .parameter("rows_per_resultset", 10)
+            .multi_statement_count(0);
+
+        assert_eq!(
+            options.to_parameters("json"),
+            json!({
+                "PYTHON_CONNECTOR_QUERY_RESULT_FORMAT": "json",
+                "QUERY_TAG": "etl",
+                "STATEMENT_TIMEOUT_IN_SECONDS": 90,
+                "ROWS_PER_RESULTSET": 10,
+                "MULTI_STATEMENT_COUNT": 0,
+            })
+        );
+    }
+}
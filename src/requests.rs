@@ -1,4 +1,7 @@
+use crate::bindings::Binding;
+
 use serde::Serialize;
+use std::collections::HashMap;
 
 #[derive(Debug, Serialize)]
 pub struct DataRequest<S> {
@@ -10,7 +13,18 @@ pub struct DataRequest<S> {
 pub struct LoginRequest<'a> {
     pub account_name: &'a str,
     pub login_name: &'a str,
-    pub password: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ext_authn_duo_method: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub passcode: Option<&'a str>,
+    #[serde(rename = "passcodeInPassword", skip_serializing_if = "Option::is_none")]
+    pub passcode_in_password: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authenticator: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<&'a str>,
     pub client_app_id: &'a str,
     pub client_app_version: &'a str,
     pub session_parameters: Option<serde_json::Value>,
@@ -24,4 +38,63 @@ pub struct QueryRequest<'a> {
     pub query_submission_time: i64,
     pub sequence_id: u32,
     pub sql_text: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bindings: Option<&'a HashMap<String, Binding>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenRequest<'a> {
+    pub old_session_token: &'a str,
+    pub request_type: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AbortRequest<'a> {
+    pub sql_text: &'a str,
+    pub request_id: &'a str,
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_login_request() {
+        let mut req = LoginRequest {
+            account_name: "account",
+            login_name: "user",
+            password: Some("password123456"),
+            ext_authn_duo_method: Some("passcode"),
+            passcode: None,
+            passcode_in_password: Some(true),
+            authenticator: None,
+            token: None,
+            client_app_id: "PythonConnector",
+            client_app_version: "2.9.0",
+            session_parameters: None,
+        };
+        assert_eq!(
+            serde_json::to_value(DataRequest { data: &req }).unwrap(),
+            json!({
+                "data": {
+                    "ACCOUNT_NAME": "account",
+                    "LOGIN_NAME": "user",
+                    "PASSWORD": "password123456",
+                    "EXT_AUTHN_DUO_METHOD": "passcode",
+                    "passcodeInPassword": true,
+                    "CLIENT_APP_ID": "PythonConnector",
+                    "CLIENT_APP_VERSION": "2.9.0",
+                    "SESSION_PARAMETERS": null,
+                }
+            })
+        );
+
+        req.passcode_in_password = None;
+        let body = serde_json::to_value(DataRequest { data: &req }).unwrap();
+        assert!(body["data"].get("passcodeInPassword").is_none());
+    }
 }
@@ -6,6 +6,7 @@ pub mod types;
 use crate::errors::SnowflakeError;
 use crate::responses::types::internal::InternalResult;
 use crate::session::Session;
+use crate::utils::https_proxy;
 
 use anyhow::anyhow;
 use async_trait::async_trait;
@@ -14,16 +15,34 @@ use std::{collections::HashMap, time::Duration};
 
 #[async_trait]
 pub trait QueryResult: deserializer::QueryDeserializer + serializer::QuerySerializer + Sized {
-    fn new(res: &InternalResult, rowset: &[Self::ReturnType], session: &Session) -> Self;
+    fn new(res: &InternalResult, rowset: Vec<Self::ReturnType>, session: &Session) -> Self;
 }
 
-pub(crate) fn get_query_detail_url(session: &Session, query_id: &String) -> String {
-    let components: Vec<String> = [session.region.clone(), Some(session.account.clone())]
+pub(crate) fn get_query_detail_url(session: &Session, query_id: &str) -> Option<String> {
+    query_detail_url(&session.host, session.region.as_deref(), &session.account, query_id)
+}
+
+/// Links to Snowsight for the public Snowflake domains. PrivateLink and custom hosts don't serve Snowsight,
+/// so there is no link for them.
+fn query_detail_url(host: &str, region: Option<&str>, account: &str, query_id: &str) -> Option<String> {
+    let domain = reqwest::Url::parse(host)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_owned))
+        .unwrap_or_default();
+
+    let app = match domain {
+        d if d.contains(".privatelink.") => return None,
+        d if d.ends_with(".snowflakecomputing.com") => "https://app.snowflake.com",
+        d if d.ends_with(".snowflakecomputing.cn") => "https://app.snowflake.cn",
+        _ => return None,
+    };
+
+    let path = [region, Some(account)]
         .into_iter()
         .flatten()
-        .collect();
-    let path = components.join("/");
-    format!("https://app.snowflake.com/{path}/#/compute/history/queries/{query_id}/detail")
+        .collect::<Vec<_>>()
+        .join("/");
+    Some(format!("{app}/{path}/#/compute/history/queries/{query_id}/detail"))
 }
 
 pub(self) fn default_chunk_headers(encryption_key: &str) -> Result<HeaderMap, anyhow::Error> {
@@ -73,8 +92,45 @@ pub(super) fn make_chunk_downloader(
     }
 
     if let Some(proxy) = &session.proxy {
-        builder = builder.proxy(reqwest::Proxy::https(proxy).unwrap());
+        builder = builder.proxy(https_proxy(proxy)?);
     }
 
     builder.build().map_err(|e| SnowflakeError::GeneralError(e.into()))
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_query_detail_url() {
+        assert_eq!(
+            query_detail_url(
+                "https://acc.us-east-1.snowflakecomputing.com",
+                Some("us-east-1"),
+                "acc",
+                "01"
+            ),
+            Some("https://app.snowflake.com/us-east-1/acc/#/compute/history/queries/01/detail".to_owned())
+        );
+        assert_eq!(
+            query_detail_url("https://acc.snowflakecomputing.com:443", None, "acc", "01"),
+            Some("https://app.snowflake.com/acc/#/compute/history/queries/01/detail".to_owned())
+        );
+        assert_eq!(
+            query_detail_url(
+                "https://acc.cn-north-1.snowflakecomputing.cn",
+                Some("cn-north-1"),
+                "acc",
+                "01"
+            ),
+            Some("https://app.snowflake.cn/cn-north-1/acc/#/compute/history/queries/01/detail".to_owned())
+        );
+        assert_eq!(
+            query_detail_url("https://acc.privatelink.snowflakecomputing.com", None, "acc", "01"),
+            None
+        );
+        assert_eq!(query_detail_url("http://127.0.0.1:8080", None, "acc", "01"), None);
+    }
+}
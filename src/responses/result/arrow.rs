@@ -0,0 +1,118 @@
+use crate::errors::SnowflakeError;
+use crate::responses::deserializer::QueryDeserializer;
+use crate::responses::serializer::QuerySerializer;
+use crate::responses::types::{
+    internal::InternalResult,
+    row_type::RowType,
+    stats::{QueryStats, StatementType},
+};
+use crate::responses::{get_query_detail_url, QueryResult};
+use crate::session::Session;
+
+use anyhow::anyhow;
+use arrow2::array::Array;
+use arrow2::chunk::Chunk;
+use arrow2::datatypes::Schema;
+
+/// Result keeping the Arrow batches returned by Snowflake as they are, without converting them into values.
+///
+/// Fields of the schema keep the metadata set by Snowflake, such as `logicalType`, `precision` and `scale`.
+#[derive(Clone, Debug)]
+pub struct ArrowResult {
+    pub rowtype: Vec<RowType>,
+    /// Schema of the batches, missing if the query returned no data.
+    pub schema: Option<Schema>,
+    pub chunks: Vec<Chunk<Box<dyn Array>>>,
+    pub query_id: String,
+    /// Snowsight link to the query, only available for the public Snowflake domains.
+    pub query_detail_url: Option<String>,
+    pub total: usize,
+    /// Row counts, reported for DML statements only.
+    pub stats: Option<QueryStats>,
+    pub statement_type: StatementType,
+}
+
+/// A single Arrow record batch together with the schema it was read with.
+#[derive(Clone, Debug)]
+pub struct ArrowBatch {
+    pub schema: Schema,
+    pub chunk: Chunk<Box<dyn Array>>,
+}
+
+impl QueryDeserializer for ArrowResult {
+    type ReturnType = ArrowBatch;
+
+    fn deserialize_rowset(
+        _rowset: &[Vec<serde_json::Value>],
+        _rowtype: &[RowType],
+    ) -> Result<Vec<Self::ReturnType>, SnowflakeError> {
+        Err(SnowflakeError::new_deserialization_error(anyhow!(
+            "Arrow result was requested, but Snowflake returned JSON"
+        )))
+    }
+
+    fn deserialize_arrow_chunk(
+        schema: &Schema,
+        chunk: &Chunk<Box<dyn Array>>,
+    ) -> Result<Vec<Self::ReturnType>, SnowflakeError> {
+        Ok(vec![ArrowBatch {
+            schema: schema.clone(),
+            chunk: chunk.clone(),
+        }])
+    }
+}
+
+impl QuerySerializer for ArrowResult {}
+
+impl QueryResult for ArrowResult {
+    fn new(res: &InternalResult, rowset: Vec<Self::ReturnType>, session: &Session) -> Self {
+        Self {
+            rowtype: res.rowtype.clone(),
+            schema: rowset.first().map(|batch| batch.schema.clone()),
+            chunks: rowset.into_iter().map(|batch| batch.chunk).collect(),
+            query_id: res.query_id.clone(),
+            query_detail_url: get_query_detail_url(session, &res.query_id.clone()),
+            total: res.total,
+            stats: res.stats.clone(),
+            statement_type: StatementType::from_optional_id(res.statement_type_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use arrow2::array::Int64Array;
+    use arrow2::datatypes::{DataType, Field, Metadata};
+    use arrow2::io::ipc::write::{StreamWriter, WriteOptions};
+
+    #[test]
+    fn test_deserialize_arrow_stream() -> Result<(), anyhow::Error> {
+        let metadata = Metadata::from([("logicalType".to_owned(), "FIXED".to_owned())]);
+        let schema = Schema::from(vec![Field::new("ID", DataType::Int64, false).with_metadata(metadata)]);
+
+        let mut stream = vec![];
+        let mut writer = StreamWriter::new(&mut stream, WriteOptions { compression: None });
+        writer.start(&schema, None)?;
+        for values in [vec![1, 2], vec![3]] {
+            writer.write(&Chunk::new(vec![Int64Array::from_vec(values).boxed()]), None)?;
+        }
+        writer.finish()?;
+
+        let batches = ArrowResult::deserialize_arrow_stream(&mut stream)?;
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].schema, schema);
+        assert_eq!(batches[0].schema.fields[0].metadata["logicalType"], "FIXED");
+        assert_eq!(batches[0].chunk.len(), 2);
+        assert_eq!(batches[1].chunk.len(), 1);
+
+        let ids = batches[1].chunk.columns()[0]
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .expect("Column should be an Int64Array");
+        assert_eq!(ids.value(0), 3);
+
+        Ok(())
+    }
+}
@@ -1,7 +1,11 @@
 use crate::errors::SnowflakeError;
 use crate::responses::deserializer::QueryDeserializer;
 use crate::responses::serializer::QuerySerializer;
-use crate::responses::types::{internal::InternalResult, row_type::RowType};
+use crate::responses::types::{
+    internal::InternalResult,
+    row_type::RowType,
+    stats::{QueryStats, StatementType},
+};
 use crate::responses::{get_query_detail_url, QueryResult};
 use crate::session::Session;
 
@@ -10,8 +14,12 @@ pub struct JsonVecResult {
     pub rowtype: Vec<RowType>,
     pub rowset: Vec<Vec<serde_json::Value>>,
     pub query_id: String,
-    pub query_detail_url: String,
+    /// Snowsight link to the query, only available for the public Snowflake domains.
+    pub query_detail_url: Option<String>,
     pub total: usize,
+    /// Row counts, reported for DML statements only.
+    pub stats: Option<QueryStats>,
+    pub statement_type: StatementType,
 }
 
 impl QueryDeserializer for JsonVecResult {
@@ -65,13 +73,15 @@ impl QueryDeserializer for JsonVecResult {
 impl QuerySerializer for JsonVecResult {}
 
 impl QueryResult for JsonVecResult {
-    fn new(res: &InternalResult, rowset: &[Self::ReturnType], session: &Session) -> Self {
+    fn new(res: &InternalResult, rowset: Vec<Self::ReturnType>, session: &Session) -> Self {
         Self {
             rowtype: res.rowtype.clone(),
-            rowset: rowset.to_vec(),
+            rowset,
             query_id: res.query_id.clone(),
             query_detail_url: get_query_detail_url(session, &res.query_id.clone()),
             total: res.total,
+            stats: res.stats.clone(),
+            statement_type: StatementType::from_optional_id(res.statement_type_id),
         }
     }
 }
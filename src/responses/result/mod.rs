@@ -1,4 +1,7 @@
+#[cfg(feature = "arrow")]
+pub mod arrow;
 pub mod hashmap;
 pub mod jsonmap;
 pub mod jsonvec;
+pub mod typed;
 pub mod vec;
@@ -0,0 +1,190 @@
+use crate::errors::SnowflakeError;
+use crate::responses::deserializer::QueryDeserializer;
+use crate::responses::result::vec::VecResult;
+use crate::responses::serializer::QuerySerializer;
+use crate::responses::types::{
+    internal::InternalResult,
+    row_type::RowType,
+    stats::{QueryStats, StatementType},
+    value::Value,
+};
+use crate::responses::{get_query_detail_url, QueryResult};
+use crate::session::Session;
+
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use std::sync::OnceLock;
+
+/// Matches the field named in serde's missing field errors.
+static MISSING_FIELD: OnceLock<Regex> = OnceLock::new();
+
+/// Result mapping every row into `R` by column name.
+///
+/// Snowflake returns unquoted column names in upper case, so structs usually need
+/// `#[serde(rename_all = "UPPERCASE")]` or a `#[serde(rename = "...")]` per field.
+#[derive(Clone, Debug)]
+pub struct TypedResult<R> {
+    pub rowtype: Vec<RowType>,
+    pub rowset: Vec<R>,
+    pub query_id: String,
+    /// Snowsight link to the query, only available for the public Snowflake domains.
+    pub query_detail_url: Option<String>,
+    pub total: usize,
+    /// Row counts, reported for DML statements only.
+    pub stats: Option<QueryStats>,
+    pub statement_type: StatementType,
+}
+
+impl<R: DeserializeOwned + Send + 'static> TypedResult<R> {
+    fn deserialize_row<'a>(names: impl Iterator<Item = &'a str>, values: Vec<Value>) -> Result<R, SnowflakeError> {
+        let row = names
+            .zip(values.iter())
+            .map(|(name, value)| Ok((name.to_owned(), Self::serialize_value(value)?)))
+            .collect::<Result<serde_json::Map<_, _>, serde_json::Error>>()
+            .map_err(|e| SnowflakeError::SerializationError(e.into()))?;
+        let row = serde_json::Value::Object(row);
+
+        serde_path_to_error::deserialize(&row).map_err(|e| {
+            let field = e.path().to_string();
+            let err = e.into_inner();
+            match row.get(&field) {
+                Some(value) => {
+                    SnowflakeError::new_deserialization_error_with_field_and_value(err.into(), field, value.to_string())
+                }
+                None => {
+                    let missing = MISSING_FIELD
+                        .get_or_init(|| Regex::new("missing field `(.+)`").unwrap())
+                        .captures(&err.to_string())
+                        .map(|c| c[1].to_owned());
+                    match missing {
+                        Some(field) => SnowflakeError::new_deserialization_error_with_field(err.into(), field),
+                        None => SnowflakeError::new_deserialization_error_with_value(err.into(), row.to_string()),
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl<R: DeserializeOwned + Send + 'static> QueryDeserializer for TypedResult<R> {
+    type ReturnType = R;
+
+    fn deserialize_rowset(
+        rowset: &[Vec<serde_json::Value>],
+        rowtype: &[RowType],
+    ) -> Result<Vec<Self::ReturnType>, SnowflakeError> {
+        VecResult::deserialize_rowset(rowset, rowtype)?
+            .into_iter()
+            .map(|row| Self::deserialize_row(rowtype.iter().map(|t| t.name.as_str()), row))
+            .collect()
+    }
+
+    #[cfg(feature = "arrow")]
+    fn deserialize_arrow_chunk(
+        schema: &arrow2::datatypes::Schema,
+        chunk: &arrow2::chunk::Chunk<Box<dyn arrow2::array::Array>>,
+    ) -> Result<Vec<Self::ReturnType>, SnowflakeError> {
+        VecResult::deserialize_arrow_chunk(schema, chunk)?
+            .into_iter()
+            .map(|row| Self::deserialize_row(schema.fields.iter().map(|f| f.name.as_str()), row))
+            .collect()
+    }
+}
+
+impl<R> QuerySerializer for TypedResult<R> {
+    // Integers are kept as numbers whenever they fit, so NUMBER(38, 0) columns map into integer fields.
+    fn serialize_value(val: &Value) -> Result<serde_json::Value, serde_json::Error> {
+        match val {
+            Value::I128(x) => match (i64::try_from(*x), u64::try_from(*x)) {
+                (Ok(x), _) => serde_json::to_value(x),
+                (_, Ok(x)) => serde_json::to_value(x),
+                _ => serde_json::to_value(x.to_string()),
+            },
+            Value::Nullable(Some(x)) => Self::serialize_value(x),
+            x => VecResult::serialize_value(x),
+        }
+    }
+}
+
+impl<R: DeserializeOwned + Send + 'static> QueryResult for TypedResult<R> {
+    fn new(res: &InternalResult, rowset: Vec<Self::ReturnType>, session: &Session) -> Self {
+        Self {
+            rowtype: res.rowtype.clone(),
+            rowset,
+            query_id: res.query_id.clone(),
+            query_detail_url: get_query_detail_url(session, &res.query_id.clone()),
+            total: res.total,
+            stats: res.stats.clone(),
+            statement_type: StatementType::from_optional_id(res.statement_type_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "UPPERCASE")]
+    struct User {
+        id: i64,
+        name: Option<String>,
+    }
+
+    fn row_type(name: &str, data_type: &str) -> RowType {
+        RowType {
+            data_type: data_type.to_owned(),
+            ext_type_name: None,
+            name: name.to_owned(),
+            nullable: true,
+            precision: Some(38),
+            scale: Some(0),
+            byte_length: None,
+        }
+    }
+
+    #[test]
+    fn test_deserialize_rowset() {
+        let rowtype = vec![row_type("ID", "fixed"), row_type("NAME", "text")];
+        let rowset = vec![
+            vec![serde_json::json!("1"), serde_json::json!("alice")],
+            vec![serde_json::json!("2"), serde_json::Value::Null],
+        ];
+
+        let users = TypedResult::<User>::deserialize_rowset(&rowset, &rowtype).unwrap();
+        assert_eq!(
+            users,
+            vec![
+                User {
+                    id: 1,
+                    name: Some("alice".to_owned())
+                },
+                User { id: 2, name: None },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deserialize_rowset_error_context() {
+        let rowset = vec![vec![serde_json::json!("alice"), serde_json::json!("alice")]];
+        let rowtype = vec![row_type("ID", "text"), row_type("NAME", "text")];
+        match TypedResult::<User>::deserialize_rowset(&rowset, &rowtype) {
+            Err(SnowflakeError::DeserializationError(_, Some(context))) => {
+                assert_eq!(context.field.as_deref(), Some("ID"));
+                assert_eq!(context.value.as_deref(), Some("\"alice\""));
+            }
+            res => panic!("Unexpected result {res:?}"),
+        }
+
+        let rowtype = vec![row_type("USER_ID", "fixed"), row_type("NAME", "text")];
+        let rowset = vec![vec![serde_json::json!("1"), serde_json::json!("alice")]];
+        match TypedResult::<User>::deserialize_rowset(&rowset, &rowtype) {
+            Err(SnowflakeError::DeserializationError(_, Some(context))) => {
+                assert_eq!(context.field.as_deref(), Some("ID"));
+            }
+            res => panic!("Unexpected result {res:?}"),
+        }
+    }
+}
@@ -41,8 +41,7 @@ impl Chunk {
             }
         }
 
-        let text;
-        if should_decompress {
+        let text = if should_decompress {
             let reader = body
                 .bytes_stream()
                 .map_err(|e| io::Error::new(ErrorKind::Other, e))
@@ -53,14 +52,15 @@ impl Chunk {
                 .read_to_string(&mut data)
                 .await
                 .map_err(|e| SnowflakeError::ChunkLoadingError(e.into()))?;
-            text = "[".to_owned() + &data + "]";
+            data
         }
         else {
-            text = body
-                .text()
+            body.text()
                 .await
-                .map_err(|e| SnowflakeError::ChunkLoadingError(e.into()))?;
-        }
+                .map_err(|e| SnowflakeError::ChunkLoadingError(e.into()))?
+        };
+        // Snowflake only separates the rows of a chunk with commas.
+        let text = "[".to_owned() + &text + "]";
 
         let res: Vec<Vec<serde_json::Value>> = serde_json::from_str(&text).map_err(|e| {
             log::error!("Failed to load chunk due to deserialization error.");
@@ -2,6 +2,7 @@ use serde::Deserialize;
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct DataResponse<S> {
+    pub code: Option<String>,
     pub data: S,
     pub message: Option<String>,
     pub success: bool,
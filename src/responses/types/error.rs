@@ -25,7 +25,8 @@ pub struct ErrorResult {
     pub line: Option<i32>,
     pub pos: Option<i32>,
     pub query_id: String,
-    pub query_detail_url: String,
+    /// Snowsight link to the query, only available for the public Snowflake domains.
+    pub query_detail_url: Option<String>,
 }
 
 impl ErrorResult {
@@ -53,7 +54,7 @@ pub struct InternalAsyncErrorResult {
 }
 
 impl InternalAsyncErrorResult {
-    pub(crate) fn to_error_result(&self, query_id: &String, query_detail_url: &String) -> ErrorResult {
+    pub(crate) fn to_error_result(&self, query_id: &String, query_detail_url: &Option<String>) -> ErrorResult {
         let error_message = self.error_message.clone().unwrap_or_default();
         let error_code = self.error_code.clone().unwrap_or_default();
         ErrorResult {
@@ -1,4 +1,6 @@
-use crate::responses::types::{chunk::Chunk, error::InternalAsyncErrorResult, query::QueryStatus, row_type::RowType};
+use crate::responses::types::{
+    chunk::Chunk, error::InternalAsyncErrorResult, query::QueryStatus, row_type::RowType, stats::QueryStats,
+};
 use serde::Deserialize;
 use std::collections::HashMap;
 
@@ -14,6 +16,22 @@ pub struct InternalResult {
     pub(crate) chunk_headers: Option<HashMap<String, serde_json::Value>>,
     pub(crate) qrmk: Option<String>,
     pub(crate) query_result_format: String,
+    /// Comma separated ids of the child queries of a multi-statement query.
+    pub(crate) result_ids: Option<String>,
+    pub(crate) stats: Option<QueryStats>,
+    pub(crate) statement_type_id: Option<i64>,
+    pub(crate) final_database_name: Option<String>,
+    pub(crate) final_schema_name: Option<String>,
+    pub(crate) final_role_name: Option<String>,
+    pub(crate) final_warehouse_name: Option<String>,
+    pub(crate) parameters: Option<Vec<InternalParameter>>,
+}
+
+/// Session parameter returned with login and query responses.
+#[derive(Clone, Debug, Deserialize)]
+pub struct InternalParameter {
+    pub name: String,
+    pub value: serde_json::Value,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -1,7 +1,34 @@
+use crate::responses::types::internal::InternalParameter;
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LoginResponse {
     pub token: String,
+    pub master_token: Option<String>,
+    pub validity_in_seconds: Option<i64>,
+    pub master_validity_in_seconds: Option<i64>,
+    pub mfa_token: Option<String>,
+    pub session_info: Option<LoginSessionInfo>,
+    pub parameters: Option<Vec<InternalParameter>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginSessionInfo {
+    pub database_name: Option<String>,
+    pub schema_name: Option<String>,
+    pub warehouse_name: Option<String>,
+    pub role_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenRenewalResponse {
+    pub session_token: String,
+    #[serde(rename = "validityInSecondsST")]
+    pub session_validity_in_seconds: Option<i64>,
+    pub master_token: Option<String>,
+    #[serde(rename = "validityInSecondsMT")]
+    pub master_validity_in_seconds: Option<i64>,
 }
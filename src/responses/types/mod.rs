@@ -5,4 +5,5 @@ pub mod internal;
 pub mod login;
 pub mod query;
 pub mod row_type;
+pub mod stats;
 pub mod value;
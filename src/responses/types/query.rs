@@ -1,6 +1,26 @@
+use crate::errors::SnowflakeError;
+
 use serde::Deserialize;
 use strum_macros::Display;
 
+/// Handle of a query submitted without waiting for its result.
+#[derive(Clone, Debug)]
+pub struct QueryHandle {
+    pub query_id: String,
+    /// Snowsight link to the query, only available for the public Snowflake domains.
+    pub query_detail_url: Option<String>,
+    /// Time after which Snowflake aborts the query unless its status is checked.
+    pub query_aborts_after_secs: i64,
+}
+
+/// Result of a single statement of a multi-statement query.
+#[derive(Debug)]
+pub struct StatementResult<T> {
+    pub query_id: String,
+    pub status: QueryStatus,
+    pub result: Result<T, SnowflakeError>,
+}
+
 #[derive(Clone, Display, Debug, Deserialize, PartialEq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum QueryStatus {
@@ -0,0 +1,132 @@
+use serde::Deserialize;
+use strum_macros::Display;
+
+/// Row counts of a DML statement.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryStats {
+    #[serde(default)]
+    pub num_rows_inserted: u64,
+    #[serde(default)]
+    pub num_rows_updated: u64,
+    #[serde(default)]
+    pub num_rows_deleted: u64,
+    #[serde(default)]
+    pub num_dml_duplicates: u64,
+}
+
+impl QueryStats {
+    /// Total number of rows inserted, updated or deleted.
+    pub fn affected_rows(&self) -> u64 {
+        self.num_rows_inserted + self.num_rows_updated + self.num_rows_deleted
+    }
+}
+
+/// Kind of statement executed, as reported by Snowflake in `statementTypeId`.
+#[derive(Clone, Copy, Debug, Display, PartialEq)]
+pub enum StatementType {
+    Select,
+    Insert,
+    Update,
+    Delete,
+    Merge,
+    MultiTableInsert,
+    Copy,
+    Unload,
+    /// Any other DML statement.
+    Dml,
+    AlterSession,
+    Use,
+    Show,
+    Describe,
+    /// Any other session control statement.
+    SessionControl,
+    /// Transaction control, e.g. `BEGIN` or `COMMIT`.
+    Transaction,
+    Ddl,
+    Put,
+    Get,
+    /// Any other file transfer statement.
+    FileTransfer,
+    MultiStatement,
+    Unknown(i64),
+}
+
+impl StatementType {
+    pub fn from_id(id: i64) -> Self {
+        match id {
+            0x1000..=0x1fff => StatementType::Select,
+            0x3100..=0x31ff => StatementType::Insert,
+            0x3200..=0x32ff => StatementType::Update,
+            0x3300..=0x33ff => StatementType::Delete,
+            0x3400..=0x34ff => StatementType::Merge,
+            0x3500..=0x35ff => StatementType::MultiTableInsert,
+            0x3600..=0x36ff => StatementType::Copy,
+            0x3700..=0x37ff => StatementType::Unload,
+            0x3000..=0x3fff => StatementType::Dml,
+            0x4100..=0x41ff => StatementType::AlterSession,
+            0x4300..=0x43ff => StatementType::Use,
+            0x4400..=0x44ff => StatementType::Show,
+            0x4500..=0x45ff => StatementType::Describe,
+            0x4000..=0x4fff => StatementType::SessionControl,
+            0x5000..=0x5fff => StatementType::Transaction,
+            0x6000..=0x6fff => StatementType::Ddl,
+            0x7101 => StatementType::Get,
+            0x7102 => StatementType::Put,
+            0x7000..=0x7fff => StatementType::FileTransfer,
+            0xa000 => StatementType::MultiStatement,
+            x => StatementType::Unknown(x),
+        }
+    }
+
+    pub(crate) fn from_optional_id(id: Option<i64>) -> Self {
+        id.map_or(StatementType::Unknown(0), Self::from_id)
+    }
+
+    /// Whether the statement modifies rows, in which case [`QueryStats`] are reported.
+    pub fn is_dml(&self) -> bool {
+        matches!(
+            self,
+            StatementType::Insert
+                | StatementType::Update
+                | StatementType::Delete
+                | StatementType::Merge
+                | StatementType::MultiTableInsert
+                | StatementType::Copy
+                | StatementType::Unload
+                | StatementType::Dml
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_statement_type_from_id() {
+        assert_eq!(StatementType::from_id(0x1000), StatementType::Select);
+        assert_eq!(StatementType::from_id(0x3100), StatementType::Insert);
+        assert_eq!(StatementType::from_id(0x3400), StatementType::Merge);
+        assert_eq!(StatementType::from_id(0x3800), StatementType::Dml);
+        assert_eq!(StatementType::from_id(0x4301), StatementType::Use);
+        assert_eq!(StatementType::from_id(0x5100), StatementType::Transaction);
+        assert_eq!(StatementType::from_id(0x6000), StatementType::Ddl);
+        assert_eq!(StatementType::from_id(0x7102), StatementType::Put);
+        assert_eq!(StatementType::from_id(0xa000), StatementType::MultiStatement);
+        assert_eq!(StatementType::from_id(0x9999), StatementType::Unknown(0x9999));
+        assert_eq!(StatementType::from_optional_id(None), StatementType::Unknown(0));
+        assert!(StatementType::from_id(0x3300).is_dml());
+        assert!(!StatementType::from_id(0x1000).is_dml());
+    }
+
+    #[test]
+    fn test_deserialize_stats() {
+        let stats: QueryStats = serde_json::from_str(r#"{"numRowsInserted": 3, "numDmlDuplicates": 1}"#).unwrap();
+        assert_eq!(stats.num_rows_inserted, 3);
+        assert_eq!(stats.num_rows_updated, 0);
+        assert_eq!(stats.num_dml_duplicates, 1);
+        assert_eq!(stats.affected_rows(), 3);
+    }
+}
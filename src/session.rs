@@ -1,26 +1,40 @@
+use crate::auth::tokens::{TokenManager, SESSION_GONE_CODE, SESSION_TOKEN_EXPIRED_CODE};
+use crate::bindings::{array_bindings, column_count, Params, ARRAY_BINDING_THRESHOLD};
 use crate::errors::SnowflakeError;
-use crate::requests::QueryRequest;
+use crate::options::ExecuteOptions;
+use crate::requests::{AbortRequest, QueryRequest};
+use crate::responses::result::{hashmap::HashMapResult, vec::VecResult};
+use crate::responses::types::value::Value;
 use crate::responses::types::{
     data::DataResponse,
     error::ErrorResult,
-    internal::{InternalInitAsyncQueryResult, InternalMonitoringQueriesResult, InternalResult},
-    query::QueryStatus,
+    internal::{
+        InternalInitAsyncQueryResult, InternalMonitoringQueriesResult, InternalMonitoringQueryResult,
+        InternalParameter, InternalResult,
+    },
+    login::LoginSessionInfo,
+    query::{QueryHandle, QueryStatus, StatementResult},
+    stats::StatementType,
 };
 use crate::responses::{get_query_detail_url, make_chunk_downloader, QueryResult};
+use crate::transaction::Transaction;
 
 use anyhow::anyhow;
 use chrono::prelude::*;
-use futures::StreamExt;
+use futures::{future, stream, Stream, StreamExt};
 use reqwest::header::ACCEPT;
 use reqwest::StatusCode;
 use serde::de::DeserializeOwned;
 use serde_json::json;
-use std::sync::atomic::AtomicI32;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicI32};
+use std::sync::Mutex;
 use std::time::Duration;
 use std::{
     str,
     sync::atomic::{AtomicU32, Ordering},
 };
+use tokio::task::JoinHandle;
 use tokio::time::Instant;
 
 const MAX_NO_DATA_RETRY: i32 = 24;
@@ -35,6 +49,56 @@ pub struct Session {
     pub(crate) sequence_counter: AtomicU32,
     pub(crate) max_parallel_downloads: Option<usize>,
     pub(crate) timeout: Option<Duration>,
+    pub(crate) tokens: TokenManager,
+    pub(crate) heartbeat: Option<JoinHandle<()>>,
+    pub(crate) close_on_drop: bool,
+    pub(crate) cancel_on_drop: bool,
+    submitted_queries: Mutex<HashMap<String, SubmittedQuery>>,
+    pub(crate) in_transaction: AtomicBool,
+    pending_rollback: Mutex<Option<JoinHandle<()>>>,
+    context: Mutex<SessionContext>,
+    #[cfg(feature = "mock")]
+    fixtures: Option<crate::fixtures::FixtureServer>,
+}
+
+/// Current database, schema, role, warehouse and parameters, as last reported by Snowflake.
+#[derive(Clone, Debug, Default)]
+struct SessionContext {
+    database: Option<String>,
+    schema: Option<String>,
+    role: Option<String>,
+    warehouse: Option<String>,
+    parameters: HashMap<String, serde_json::Value>,
+}
+
+impl SessionContext {
+    fn update_parameters(&mut self, parameters: Option<&Vec<InternalParameter>>) {
+        for parameter in parameters.into_iter().flatten() {
+            self.parameters
+                .insert(parameter.name.to_ascii_uppercase(), parameter.value.clone());
+        }
+    }
+
+    fn update(&mut self, internal: &InternalResult) {
+        // Responses only report what the statement could have changed, so missing values are kept.
+        let set = |current: &mut Option<String>, value: &Option<String>| {
+            if value.is_some() {
+                current.clone_from(value);
+            }
+        };
+        set(&mut self.database, &internal.final_database_name);
+        set(&mut self.schema, &internal.final_schema_name);
+        set(&mut self.role, &internal.final_role_name);
+        set(&mut self.warehouse, &internal.final_warehouse_name);
+        self.update_parameters(internal.parameters.as_ref());
+    }
+}
+
+/// Request a query was submitted with, needed to abort it.
+#[derive(Clone, Debug)]
+struct SubmittedQuery {
+    request_id: String,
+    sql_text: String,
 }
 
 impl Session {
@@ -48,6 +112,16 @@ impl Session {
         timeout: Option<Duration>,
     ) -> Self {
         Session {
+            tokens: TokenManager::new(client.clone(), host),
+            heartbeat: None,
+            close_on_drop: false,
+            cancel_on_drop: false,
+            submitted_queries: Mutex::new(HashMap::new()),
+            in_transaction: AtomicBool::new(false),
+            pending_rollback: Mutex::new(None),
+            context: Mutex::new(SessionContext::default()),
+            #[cfg(feature = "mock")]
+            fixtures: None,
             client,
             host: host.to_owned(),
             account: account.to_owned(),
@@ -59,87 +133,609 @@ impl Session {
         }
     }
 
+    /// Sets the tokens returned on login so requests can renew the session once the session token expires.
+    pub(crate) async fn with_tokens(self, token: &str, master_token: Option<&str>) -> Self {
+        self.tokens.set_tokens(token, master_token).await;
+        self
+    }
+
+    /// Sets the session context returned on login.
+    pub(crate) fn with_context(
+        self,
+        info: Option<&LoginSessionInfo>,
+        parameters: Option<&Vec<InternalParameter>>,
+    ) -> Self {
+        {
+            let mut context = self.context.lock().unwrap();
+            if let Some(info) = info {
+                context.database.clone_from(&info.database_name);
+                context.schema.clone_from(&info.schema_name);
+                context.role.clone_from(&info.role_name);
+                context.warehouse.clone_from(&info.warehouse_name);
+            }
+            context.update_parameters(parameters);
+        }
+        self
+    }
+
+    /// Keeps the server recording or replaying fixtures running for as long as the session.
+    #[cfg(feature = "mock")]
+    pub(crate) fn with_fixtures(mut self, fixtures: crate::fixtures::FixtureServer) -> Self {
+        self.fixtures = Some(fixtures);
+        self
+    }
+
+    /// Keeps the session alive by sending a heartbeat every `interval` until the session is dropped.
+    pub(crate) fn with_heartbeat(mut self, interval: Duration) -> Self {
+        let client = self.client.clone();
+        let url = self.get_session_url("heartbeat");
+        let tokens = self.tokens.clone();
+        self.heartbeat = Some(tokio::spawn(heartbeat(client, url, tokens, interval)));
+        self
+    }
+
+    /// Closes the session on a best-effort basis once it is dropped without calling [`Session::close`].
+    pub(crate) fn with_close_on_drop(mut self) -> Self {
+        self.close_on_drop = true;
+        self
+    }
+
+    /// Cancels the query on the server when the future executing it is dropped before it finishes.
+    pub(crate) fn with_cancel_on_drop(mut self) -> Self {
+        self.cancel_on_drop = true;
+        self
+    }
+
+    /// Database in use, as of the last response received by this session.
+    pub fn current_database(&self) -> Option<String> {
+        self.context.lock().unwrap().database.clone()
+    }
+
+    /// Schema in use, as of the last response received by this session.
+    pub fn current_schema(&self) -> Option<String> {
+        self.context.lock().unwrap().schema.clone()
+    }
+
+    /// Role in use, as of the last response received by this session.
+    pub fn current_role(&self) -> Option<String> {
+        self.context.lock().unwrap().role.clone()
+    }
+
+    /// Warehouse in use, as of the last response received by this session.
+    pub fn current_warehouse(&self) -> Option<String> {
+        self.context.lock().unwrap().warehouse.clone()
+    }
+
+    /// Whether a transaction is open, as of the last transaction control or DDL statement of this session.
+    pub fn in_transaction(&self) -> bool {
+        self.in_transaction.load(Ordering::SeqCst)
+    }
+
+    /// Session parameters reported by Snowflake so far, keyed by upper case name.
+    pub fn parameters(&self) -> HashMap<String, serde_json::Value> {
+        self.context.lock().unwrap().parameters.clone()
+    }
+
+    /// Sets a session parameter with `ALTER SESSION`, or unsets it when `value` is null.
+    pub async fn set_parameter(&self, name: &str, value: impl Into<serde_json::Value>) -> Result<(), SnowflakeError> {
+        let name = Self::parameter_name(name)?;
+        let value = value.into();
+        let query = match &value {
+            serde_json::Value::Null => format!("ALTER SESSION UNSET {name}"),
+            value => format!("ALTER SESSION SET {name} = {}", Self::parameter_literal(value)?),
+        };
+        let _: VecResult = self.execute(&query).await?;
+
+        let mut context = self.context.lock().unwrap();
+        match value {
+            serde_json::Value::Null => context.parameters.remove(&name),
+            value => context.parameters.insert(name, value),
+        };
+        Ok(())
+    }
+
+    /// Returns the current value of a session parameter using `SHOW PARAMETERS`, e.g. as `String`, `i64`, `bool`
+    /// or `serde_json::Value`.
+    pub async fn get_parameter<T: DeserializeOwned>(&self, name: &str) -> Result<Option<T>, SnowflakeError> {
+        let name = Self::parameter_name(name)?;
+        let res: HashMapResult = self
+            .execute(&format!("SHOW PARAMETERS LIKE '{name}' IN SESSION"))
+            .await?;
+
+        // `_` is a wildcard in LIKE patterns, so only the exact key is taken.
+        let row = match res
+            .rowset
+            .iter()
+            .find(|row| row.get("key").map(Value::to_string).as_deref() == Some(name.as_str()))
+        {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+        let value = Self::parameter_value(
+            &row.get("value").map(Value::to_string).unwrap_or_default(),
+            &row.get("type").map(Value::to_string).unwrap_or_default(),
+        );
+        serde_json::from_value(value.clone())
+            .map(Some)
+            .map_err(|e| SnowflakeError::new_deserialization_error_with_value(e.into(), value.to_string()))
+    }
+
+    /// Converts a value listed by `SHOW PARAMETERS` according to its type.
+    fn parameter_value(value: &str, parameter_type: &str) -> serde_json::Value {
+        let parsed = match parameter_type {
+            "BOOLEAN" => value.parse::<bool>().ok().map(serde_json::Value::from),
+            "NUMBER" => value
+                .parse::<i64>()
+                .map(serde_json::Value::from)
+                .ok()
+                .or_else(|| value.parse::<f64>().ok().map(serde_json::Value::from)),
+            _ => None,
+        };
+        parsed.unwrap_or_else(|| serde_json::Value::from(value))
+    }
+
+    /// Validates a parameter name, which can't be bound and is formatted into the statement.
+    fn parameter_name(name: &str) -> Result<String, SnowflakeError> {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(SnowflakeError::ExecutionError(
+                anyhow!("Invalid session parameter name '{name}'"),
+                None,
+            ));
+        }
+        Ok(name.to_ascii_uppercase())
+    }
+
+    fn parameter_literal(value: &serde_json::Value) -> Result<String, SnowflakeError> {
+        match value {
+            serde_json::Value::String(x) => Ok(format!("'{}'", x.replace('\\', "\\\\").replace('\'', "\\'"))),
+            serde_json::Value::Bool(_) | serde_json::Value::Number(_) => Ok(value.to_string()),
+            x => Err(SnowflakeError::ExecutionError(
+                anyhow!("Unsupported session parameter value {x}"),
+                None,
+            )),
+        }
+    }
+
+    /// Logs out and ends the session on the server.
+    pub async fn close(mut self) -> Result<(), SnowflakeError> {
+        self.close_on_drop = false;
+        if let Some(heartbeat) = self.heartbeat.take() {
+            heartbeat.abort();
+        }
+        delete_session(&self.client, &self.get_session_delete_url(), &self.tokens).await
+    }
+
     pub async fn execute_async<T: QueryResult + Send + Sync>(&self, query: &str) -> Result<T, SnowflakeError> {
         let start_ts = Instant::now();
-        let init_res: InternalInitAsyncQueryResult = self.execute_query_request(query, true, start_ts).await?;
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let guard = self.abort_on_drop(&request_id, query);
 
-        self.await_async_query(query, &init_res, start_ts).await?;
-        let query_id = &init_res.query_id;
-        self.execute_impl(&format!("select * from table(result_scan('{query_id}'))"), start_ts)
+        let res = match self
+            .submit_impl(query, &ExecuteOptions::new(), &request_id, start_ts)
             .await
+        {
+            Ok(handle) => self.fetch_result_impl(&handle.query_id, start_ts).await,
+            Err(e) => Err(e),
+        };
+        guard.disarm();
+        res
+    }
+
+    /// Starts a transaction, which is rolled back unless committed.
+    ///
+    /// Only one transaction can be active on a session at a time.
+    pub async fn begin(&self) -> Result<Transaction<'_>, SnowflakeError> {
+        Transaction::begin(self).await
+    }
+
+    /// Submits a query without waiting for it to finish.
+    ///
+    /// The query keeps running after the session is dropped, and its result can be fetched by id from any
+    /// session of the same user with [`Session::fetch_result`].
+    pub async fn submit(&self, query: &str) -> Result<QueryHandle, SnowflakeError> {
+        self.submit_with_options(query, ExecuteOptions::new()).await
+    }
+
+    /// Submits a query with the given options without waiting for it to finish, see [`Session::submit`].
+    pub async fn submit_with_options(
+        &self,
+        query: &str,
+        options: ExecuteOptions,
+    ) -> Result<QueryHandle, SnowflakeError> {
+        self.submit_impl(query, &options, &uuid::Uuid::new_v4().to_string(), Instant::now())
+            .await
+    }
+
+    /// Returns the current status of a query submitted with [`Session::submit`].
+    pub async fn query_status(&self, query_id: &str) -> Result<QueryStatus, SnowflakeError> {
+        let query_id = query_id.to_owned();
+        let query_result = self.get_monitoring_query_result(&query_id).await.map_err(|e| match e {
+            backoff::Error::Permanent(e) => e,
+            backoff::Error::Transient { err, .. } => err,
+        })?;
+        let status = query_result.map_or(QueryStatus::NoData, |res| res.status);
+        // Finished queries can no longer be aborted, so there is no need to keep their request.
+        if matches!(
+            status,
+            QueryStatus::Success
+                | QueryStatus::FailedWithError
+                | QueryStatus::Aborted
+                | QueryStatus::FailedWithIncident
+                | QueryStatus::Disconnected
+        ) {
+            self.submitted_queries.lock().unwrap().remove(&query_id);
+        }
+        Ok(status)
+    }
+
+    /// Cancels a running query.
+    ///
+    /// Queries submitted from this session are aborted using the request they were submitted with,
+    /// any other query is cancelled with `SYSTEM$CANCEL_QUERY`.
+    pub async fn cancel(&self, query_id: &str) -> Result<(), SnowflakeError> {
+        let submitted = self.submitted_queries.lock().unwrap().get(query_id).cloned();
+        match submitted {
+            Some(submitted) => {
+                let url = self.get_queries_url("abort-request", &uuid::Uuid::new_v4().to_string());
+                abort_request(&self.client, &url, &self.tokens, &submitted).await?;
+                self.submitted_queries.lock().unwrap().remove(query_id);
+                Ok(())
+            }
+            None => {
+                let params = vec![Value::String(query_id.to_owned())];
+                let _: VecResult = self
+                    .execute_with_params("SELECT SYSTEM$CANCEL_QUERY(?)", params)
+                    .await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Waits for a query submitted with [`Session::submit`] to finish and fetches its result.
+    pub async fn fetch_result<T: QueryResult + Send + Sync>(&self, query_id: &str) -> Result<T, SnowflakeError> {
+        self.fetch_result_impl(query_id, Instant::now()).await
+    }
+
+    async fn submit_impl(
+        &self,
+        query: &str,
+        options: &ExecuteOptions,
+        request_id: &str,
+        start_ts: Instant,
+    ) -> Result<QueryHandle, SnowflakeError> {
+        let options = options.clone().async_exec();
+        let init_res: InternalInitAsyncQueryResult = self
+            .execute_query_request(query, &options, request_id, start_ts)
+            .await?;
+
+        self.sequence_counter.fetch_add(1, Ordering::Relaxed);
+        self.submitted_queries.lock().unwrap().insert(
+            init_res.query_id.clone(),
+            SubmittedQuery {
+                request_id: request_id.to_owned(),
+                sql_text: query.to_owned(),
+            },
+        );
+
+        Ok(QueryHandle {
+            query_detail_url: get_query_detail_url(self, &init_res.query_id),
+            query_id: init_res.query_id,
+            query_aborts_after_secs: init_res.query_aborts_after_secs,
+        })
+    }
+
+    async fn fetch_result_impl<T: QueryResult + Send + Sync>(
+        &self,
+        query_id: &str,
+        start_ts: Instant,
+    ) -> Result<T, SnowflakeError> {
+        let res = self.await_async_query(query_id, start_ts).await;
+        let submitted = self.submitted_queries.lock().unwrap().remove(query_id);
+        res?;
+
+        let internal = self.get_query_result(query_id, start_ts).await?;
+        // Results of queries submitted elsewhere don't describe this session.
+        if let Some(submitted) = submitted {
+            self.update_context(&submitted.sql_text, &internal).await?;
+        }
+        self.load_result(&internal, start_ts).await
     }
 
     pub async fn execute<T: QueryResult + Send + Sync>(&self, query: &str) -> Result<T, SnowflakeError> {
-        self.execute_impl(query, Instant::now()).await
+        self.execute_impl(query, &ExecuteOptions::new(), Instant::now()).await
+    }
+
+    /// Executes a query with values bound to its `?`, `:1` or `:name` placeholders.
+    pub async fn execute_with_params<T: QueryResult + Send + Sync>(
+        &self,
+        query: &str,
+        params: impl Into<Params>,
+    ) -> Result<T, SnowflakeError> {
+        self.execute_impl(query, &ExecuteOptions::new().params(params), Instant::now())
+            .await
+    }
+
+    /// Executes a query with the given options, e.g. bound values or a `QUERY_TAG`.
+    pub async fn execute_with_options<T: QueryResult + Send + Sync>(
+        &self,
+        query: &str,
+        options: ExecuteOptions,
+    ) -> Result<T, SnowflakeError> {
+        self.execute_impl(query, &options, Instant::now()).await
+    }
+
+    /// Executes a DML statement such as `INSERT INTO t VALUES (?, ?)` once for every row in `rows`,
+    /// returning the number of affected rows.
+    ///
+    /// Rows are bound column-wise, so a single request is sent for up to [`ARRAY_BINDING_THRESHOLD`] values.
+    /// Larger batches are split into several requests, run in a transaction unless one is already open, so the
+    /// rows are inserted all or nothing.
+    pub async fn execute_many(&self, query: &str, rows: &[Vec<Value>]) -> Result<u64, SnowflakeError> {
+        if rows.is_empty() {
+            return Ok(0);
+        }
+
+        let columns = column_count(rows)?;
+        let batches = rows
+            .chunks((ARRAY_BINDING_THRESHOLD / columns.max(1)).max(1))
+            .map(array_bindings)
+            .collect::<Result<Vec<_>, _>>()?;
+        let transaction = match batches.len() > 1 && !self.in_transaction() {
+            true => Some(self.begin().await?),
+            false => None,
+        };
+
+        let mut affected_rows = 0;
+        for bindings in batches {
+            let options = ExecuteOptions::new().bindings(bindings);
+            let res: VecResult = self.execute_impl(query, &options, Instant::now()).await?;
+            affected_rows += Self::affected_rows(&res)?;
+        }
+
+        if let Some(transaction) = transaction {
+            transaction.commit().await?;
+        }
+        Ok(affected_rows)
+    }
+
+    /// Row count of a DML result, taken from the reported stats or else from the first column of its first row,
+    /// which holds the number of inserted, updated or deleted rows.
+    fn affected_rows(res: &VecResult) -> Result<u64, SnowflakeError> {
+        if let Some(stats) = &res.stats {
+            return Ok(stats.affected_rows());
+        }
+
+        let count = |value: &Value| {
+            let count = match value {
+                Value::I64(x) => u64::try_from(*x).ok(),
+                Value::I128(x) => u64::try_from(*x).ok(),
+                Value::Decimal(x) => u64::try_from(*x).ok(),
+                _ => Some(0),
+            };
+            count.ok_or_else(|| SnowflakeError::ExecutionError(anyhow!("Invalid affected row count {value}"), None))
+        };
+
+        match res.rowset.first().and_then(|row| row.first()) {
+            Some(Value::Nullable(Some(v))) => count(v),
+            Some(v) => count(v),
+            None => Ok(0),
+        }
     }
 
     async fn execute_impl<T: QueryResult + Send + Sync>(
         &self,
         query: &str,
+        options: &ExecuteOptions,
+        start_ts: Instant,
+    ) -> Result<T, SnowflakeError> {
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let guard = self.abort_on_drop(&request_id, query);
+        let res = self.execute_query_request(query, options, &request_id, start_ts).await;
+        guard.disarm();
+        let internal: InternalResult = res?;
+
+        self.sequence_counter.fetch_add(1, Ordering::Relaxed);
+        self.update_context(query, &internal).await?;
+
+        self.load_result(&internal, start_ts).await
+    }
+
+    /// Tracks the database, schema, role, warehouse, parameters and transaction of the session from the response
+    /// to `query`.
+    async fn update_context(&self, query: &str, internal: &InternalResult) -> Result<(), SnowflakeError> {
+        self.context.lock().unwrap().update(internal);
+        match StatementType::from_optional_id(internal.statement_type_id) {
+            StatementType::Transaction => {
+                if let Some(active) = Transaction::opens_transaction(query) {
+                    self.in_transaction.store(active, Ordering::SeqCst);
+                }
+            }
+            // Any statement of a script may start or end a transaction, so the state is checked after it instead.
+            StatementType::MultiStatement => self.refresh_transaction_state().await?,
+            // DDL statements commit the open transaction.
+            StatementType::Ddl => self.in_transaction.store(false, Ordering::SeqCst),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Checks whether a transaction is open after a script.
+    async fn refresh_transaction_state(&self) -> Result<(), SnowflakeError> {
+        let start_ts = Instant::now();
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let internal: InternalResult = self
+            .execute_query_request(
+                "SELECT CURRENT_TRANSACTION()",
+                &ExecuteOptions::new(),
+                &request_id,
+                start_ts,
+            )
+            .await?;
+        self.sequence_counter.fetch_add(1, Ordering::Relaxed);
+
+        let res: VecResult = self.load_result(&internal, start_ts).await?;
+        let transaction = res.rowset.first().and_then(|row| row.first());
+        let active = !matches!(transaction, None | Some(Value::Nullable(None)));
+        self.in_transaction.store(active, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Deserializes the rows returned with `internal` and downloads its remaining chunks.
+    async fn load_result<T: QueryResult + Send + Sync>(
+        &self,
+        internal: &InternalResult,
         start_ts: Instant,
     ) -> Result<T, SnowflakeError> {
-        let internal: InternalResult = self.execute_query_request(query, false, start_ts).await?;
+        let mut rowset = Self::deserialize_first_rowset::<T>(internal)?;
+
+        let timeout = self.get_remaining_timeout(start_ts);
+        let mut chunks = self.download_chunks::<T>(internal, timeout)?;
+        while let Some(chunk) = chunks.next().await {
+            rowset.extend(&mut chunk?.into_iter());
+
+            // This timeout is passed to the reqwest client, but because it's buffered the timeout may extend past the timeout.
+            if let Some(Duration::ZERO) = self.get_remaining_timeout(start_ts) {
+                return Err(SnowflakeError::ExecutionError(
+                    anyhow!("Request timed out after {:#?}", self.timeout.unwrap()),
+                    None,
+                ));
+            }
+        }
+        Ok(T::new(internal, rowset, self))
+    }
+
+    /// Executes a script of `;` separated statements, returning one result per statement in order.
+    ///
+    /// Execution stops at the first failing statement, in which case an error is returned for the whole script.
+    pub async fn execute_multi<T: QueryResult + Send + Sync>(
+        &self,
+        query: &str,
+    ) -> Result<Vec<StatementResult<T>>, SnowflakeError> {
+        let start_ts = Instant::now();
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let guard = self.abort_on_drop(&request_id, query);
+        let options = ExecuteOptions::new().multi_statement_count(0);
+        let res = self.execute_query_request(query, &options, &request_id, start_ts).await;
+        guard.disarm();
+        let internal: InternalResult = res?;
 
         self.sequence_counter.fetch_add(1, Ordering::Relaxed);
+        self.update_context(query, &internal).await?;
+
+        let result_ids = match &internal.result_ids {
+            Some(ids) => ids.split(',').filter(|id| !id.is_empty()).collect::<Vec<_>>(),
+            None => {
+                return Ok(vec![StatementResult {
+                    query_id: internal.query_id.clone(),
+                    status: QueryStatus::Success,
+                    result: self.load_result(&internal, start_ts).await,
+                }])
+            }
+        };
 
-        let mut rowset;
+        let mut results = Vec::with_capacity(result_ids.len());
+        for query_id in result_ids {
+            let result = match self.get_query_result(query_id, start_ts).await {
+                // The transaction state was already checked after the script.
+                Ok(child) => {
+                    self.context.lock().unwrap().update(&child);
+                    self.load_result(&child, start_ts).await
+                }
+                Err(e) => Err(e),
+            };
+            let status = match &result {
+                Ok(_) => QueryStatus::Success,
+                Err(_) => self.query_status(query_id).await.unwrap_or(QueryStatus::NoData),
+            };
+            results.push(StatementResult {
+                query_id: query_id.to_owned(),
+                status,
+                result,
+            });
+        }
+        Ok(results)
+    }
+
+    /// Executes a query and streams its rows in batches as they are downloaded, instead of collecting
+    /// every chunk in memory first.
+    ///
+    /// Chunks are downloaded in order with up to `max_parallel_downloads` in flight, and no further chunks
+    /// are requested until buffered ones are consumed.
+    pub async fn execute_stream<T: QueryResult + Send + Sync>(
+        &self,
+        query: &str,
+    ) -> Result<impl Stream<Item = Result<Vec<T::ReturnType>, SnowflakeError>>, SnowflakeError> {
+        let start_ts = Instant::now();
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let guard = self.abort_on_drop(&request_id, query);
+        let res = self
+            .execute_query_request(query, &ExecuteOptions::new(), &request_id, start_ts)
+            .await;
+        guard.disarm();
+        let internal: InternalResult = res?;
+
+        self.sequence_counter.fetch_add(1, Ordering::Relaxed);
+        self.update_context(query, &internal).await?;
+
+        let rowset = Self::deserialize_first_rowset::<T>(&internal)?;
+        let chunks = self.download_chunks::<T>(&internal, self.get_remaining_timeout(start_ts))?;
+        Ok(stream::once(future::ready(Ok(rowset))).chain(chunks))
+    }
+
+    /// Deserializes the rows returned inline with the query response.
+    fn deserialize_first_rowset<T: QueryResult>(
+        internal: &InternalResult,
+    ) -> Result<Vec<T::ReturnType>, SnowflakeError> {
         if let Some(r) = &internal.rowset_base64 {
-            rowset = T::deserialize_rowset64(r)?;
+            T::deserialize_rowset64(r)
         }
         else if let Some(r) = &internal.rowset {
-            rowset = T::deserialize_rowset(r, &internal.rowtype)?;
+            T::deserialize_rowset(r, &internal.rowtype)
         }
         else {
-            return Err(SnowflakeError::new_deserialization_error_with_value(
+            Err(SnowflakeError::new_deserialization_error_with_value(
                 anyhow!("Missing rowsetBase64 or rowset for Arrow format"),
                 format!("{:?}", internal),
-            ));
+            ))
         }
+    }
 
-        let timeout = self.get_remaining_timeout(start_ts);
-        if let Some(chunks) = internal.chunks.clone() {
-            let downloader = make_chunk_downloader(self, &internal, timeout)?;
-            let mut buffered_chunks_futures = tokio_stream::iter(chunks)
-                .map(|chunk| {
-                    let task_query_result_format = internal.query_result_format.clone();
-                    let task_downloader = downloader.clone();
-                    let task_row_type = internal.rowtype.clone();
-                    tokio::spawn(async move {
-                        log::debug!("Downloading chunk at url: {}", chunk.url);
-                        match task_query_result_format.as_str() {
-                            "arrow" => chunk.load_arrow::<T>(&task_downloader).await,
-                            "json" => chunk.load_json::<T>(&task_downloader, &task_row_type).await,
-                            x => Err(SnowflakeError::ChunkLoadingError(anyhow!(
-                                "Unsupported query result format {x}"
-                            ))),
-                        }
-                    })
+    /// Downloads the remaining chunks of a result in order, with up to `max_parallel_downloads` in flight.
+    fn download_chunks<T: QueryResult + Send + Sync>(
+        &self,
+        internal: &InternalResult,
+        timeout: Option<Duration>,
+    ) -> Result<impl Stream<Item = Result<Vec<T::ReturnType>, SnowflakeError>>, SnowflakeError> {
+        let chunks = internal.chunks.clone().unwrap_or_default();
+        let downloader = match chunks.is_empty() {
+            true => None,
+            false => Some(make_chunk_downloader(self, internal, timeout)?),
+        };
+        let query_result_format = internal.query_result_format.clone();
+        let row_type = internal.rowtype.clone();
+
+        Ok(tokio_stream::iter(chunks)
+            .map(move |chunk| {
+                let task_query_result_format = query_result_format.clone();
+                let task_downloader = downloader.clone().expect("Chunk downloader should exist for chunks");
+                let task_row_type = row_type.clone();
+                tokio::spawn(async move {
+                    log::debug!("Downloading chunk at url: {}", chunk.url);
+                    match task_query_result_format.as_str() {
+                        "arrow" => chunk.load_arrow::<T>(&task_downloader).await,
+                        "json" => chunk.load_json::<T>(&task_downloader, &task_row_type).await,
+                        x => Err(SnowflakeError::ChunkLoadingError(anyhow!(
+                            "Unsupported query result format {x}"
+                        ))),
+                    }
                 })
-                .buffered(self.max_parallel_downloads.unwrap_or(1));
-
-            while let Some(joined_chunk) = buffered_chunks_futures.next().await {
-                let chunk = joined_chunk.map_err(|e| SnowflakeError::ExecutionError(e.into(), None))??;
-                rowset.extend(&mut chunk.into_iter());
-
-                // This timeout is passed to the reqwest client, but because it's buffered the timeout may extend past the timeout.
-                if let Some(Duration::ZERO) = self.get_remaining_timeout(start_ts) {
-                    return Err(SnowflakeError::ExecutionError(
-                        anyhow!("Request timed out after {:#?}", self.timeout.unwrap()),
-                        None,
-                    ));
-                }
-            }
-        }
-        Ok(T::new(&internal, &rowset, self))
+            })
+            .buffered(self.max_parallel_downloads.unwrap_or(1))
+            .map(|joined_chunk| joined_chunk.map_err(|e| SnowflakeError::ExecutionError(e.into(), None))?))
     }
 
-    async fn await_async_query(
-        &self,
-        query: &str,
-        init_async_query_res: &InternalInitAsyncQueryResult,
-        start_ts: Instant,
-    ) -> Result<(), SnowflakeError> {
-        let query_id = &init_async_query_res.query_id;
+    async fn await_async_query(&self, query_id: &str, start_ts: Instant) -> Result<(), SnowflakeError> {
+        let query_id = &query_id.to_owned();
         log::debug!("Awaiting async snowflake query '{query_id}'");
 
         let backoff = backoff::ExponentialBackoffBuilder::new()
@@ -152,44 +748,8 @@ impl Session {
         let start_time = Instant::now();
 
         let request_op = || async {
-            let json = self
-                .client
-                .get(&self.get_monitoring_queries_url(query_id))
-                // Monitoring queries uses ACCEPT - JSON. Reqwest client wont' override this.
-                .header(ACCEPT, "application/json")
-                .build()
-                .map_err(|e| SnowflakeError::ExecutionError(e.into(), None))
-                .map_err(backoff::Error::Permanent)?;
-
-            let body = self
-                .client
-                .execute(json)
-                .await
-                .map_err(|e| SnowflakeError::ExecutionError(e.into(), None))
-                .map_err(backoff::Error::Permanent)?;
-
-            let status = body.status();
-
-            let text = body
-                .text()
-                .await
-                .map_err(|e| SnowflakeError::ExecutionError(e.into(), None))
-                .map_err(backoff::Error::Permanent)?;
-
-            self.parse_response_status_with_retry(&status, &text, Some(query_id.clone()))?;
-
-            let res: DataResponse<serde_json::Value> = serde_json::from_str(&text)
-                .map_err(|e| {
-                    log::error!("Failed to execute monitoring query {query_id} due to deserialization error.");
-                    SnowflakeError::new_deserialization_error_with_value(e.into(), text)
-                })
-                .map_err(backoff::Error::Permanent)?;
-
-            let monitoring_result: InternalMonitoringQueriesResult = serde_json::from_value(res.data.clone())
-                .map_err(|e| SnowflakeError::new_deserialization_error_with_value(e.into(), res.data.to_string()))
-                .map_err(backoff::Error::Permanent)?;
-
-            let query_result = monitoring_result.queries.get(0);
+            let query_result = self.get_monitoring_query_result(query_id).await?;
+            let query_result = query_result.as_ref();
             let query_status = query_result.map_or(QueryStatus::NoData, |res| res.status.clone());
 
             if !query_status.is_still_running() {
@@ -241,22 +801,127 @@ impl Session {
         .await
     }
 
+    /// Fetches the status of a query from the monitoring endpoint, if Snowflake knows about it yet.
+    async fn get_monitoring_query_result(
+        &self,
+        query_id: &String,
+    ) -> Result<Option<InternalMonitoringQueryResult>, backoff::Error<SnowflakeError>> {
+        let json = self
+            .tokens
+            .authorize(self.client.get(&self.get_monitoring_queries_url(query_id)))
+            .await
+            // Monitoring queries uses ACCEPT - JSON. Reqwest client wont' override this.
+            .header(ACCEPT, "application/json")
+            .build()
+            .map_err(|e| SnowflakeError::ExecutionError(e.into(), None))
+            .map_err(backoff::Error::Permanent)?;
+
+        let body = self
+            .client
+            .execute(json)
+            .await
+            .map_err(|e| SnowflakeError::ExecutionError(e.into(), None))
+            .map_err(backoff::Error::Permanent)?;
+
+        let status = body.status();
+
+        let text = body
+            .text()
+            .await
+            .map_err(|e| SnowflakeError::ExecutionError(e.into(), None))
+            .map_err(backoff::Error::Permanent)?;
+
+        self.parse_response_status_with_retry(&status, &text, Some(query_id.clone()))?;
+
+        let res: DataResponse<serde_json::Value> = serde_json::from_str(&text)
+            .map_err(|e| {
+                log::error!("Failed to execute monitoring query {query_id} due to deserialization error.");
+                SnowflakeError::new_deserialization_error_with_value(e.into(), text)
+            })
+            .map_err(backoff::Error::Permanent)?;
+
+        let monitoring_result: InternalMonitoringQueriesResult = serde_json::from_value(res.data.clone())
+            .map_err(|e| SnowflakeError::new_deserialization_error_with_value(e.into(), res.data.to_string()))
+            .map_err(backoff::Error::Permanent)?;
+
+        Ok(monitoring_result.queries.into_iter().next())
+    }
+
     async fn execute_query_request<T: DeserializeOwned>(
         &self,
         query: &str,
-        async_exec: bool,
+        options: &ExecuteOptions,
+        request_id: &str,
         start_ts: Instant,
     ) -> Result<T, SnowflakeError> {
+        let pending_rollback = self.pending_rollback.lock().unwrap().take();
+        if let Some(rollback) = pending_rollback {
+            _ = rollback.await;
+        }
+
         let now = Utc::now();
         let req = QueryRequest {
-            async_exec,
-            parameters: Some(json!({ "PYTHON_CONNECTOR_QUERY_RESULT_FORMAT": Self::result_format() })),
+            async_exec: options.async_exec,
+            parameters: Some(options.to_parameters(Self::result_format())),
             query_submission_time: now.timestamp_millis(),
             sequence_id: self.sequence_counter.load(Ordering::Relaxed),
             sql_text: query,
+            bindings: options.bindings.as_ref(),
         };
-        let query_url = self.get_queries_url("query-request");
+        let query_url = self.get_queries_url("query-request", request_id);
+        self.query_api_request(query, &query_url, Some(&req), start_ts).await
+    }
 
+    /// Fetches the result of a finished query, as it would have been returned when executing it.
+    async fn get_query_result(&self, query_id: &str, start_ts: Instant) -> Result<InternalResult, SnowflakeError> {
+        let result_url = self.get_query_result_url(query_id);
+        self.query_api_request(query_id, &result_url, None, start_ts).await
+    }
+
+    /// Sends a request to the queries API, posting `req` if given, and parses the returned data.
+    async fn query_api_request<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        url: &str,
+        req: Option<&QueryRequest<'_>>,
+        start_ts: Instant,
+    ) -> Result<T, SnowflakeError> {
+        let token = self.tokens.session_token().await;
+        let mut res = self.send_query_request(query, url, req, start_ts).await?;
+
+        if res.code.as_deref() == Some(SESSION_TOKEN_EXPIRED_CODE) {
+            if let Some(token) = token {
+                log::info!("Session token expired, renewing session.");
+                self.tokens.renew(&token).await?;
+                res = self.send_query_request(query, url, req, start_ts).await?;
+            }
+        }
+
+        if !res.success {
+            let err = ErrorResult::deserialize(res.data, self)?;
+            if let Some(message) = res.message {
+                return Err(SnowflakeError::ExecutionError(anyhow!(message), Some(err)));
+            }
+            else {
+                return Err(SnowflakeError::ExecutionError(
+                    anyhow!("No reason given by Snowflake API"),
+                    Some(err),
+                ));
+            }
+        }
+
+        let parsed: T = serde_json::from_value(res.data.clone())
+            .map_err(|e| SnowflakeError::new_deserialization_error_with_value(e.into(), res.data.to_string()))?;
+        Ok(parsed)
+    }
+
+    async fn send_query_request(
+        &self,
+        query: &str,
+        query_url: &str,
+        req: Option<&QueryRequest<'_>>,
+        start_ts: Instant,
+    ) -> Result<DataResponse<serde_json::Value>, SnowflakeError> {
         // https://github.com/snowflakedb/snowflake-connector-python/blob/f0a38d958c82bf039765faee7050c89d2ccb1d72/src/snowflake/connector/network.py#L791
         let backoff = backoff::ExponentialBackoffBuilder::new()
             .with_initial_interval(Duration::from_secs(1))
@@ -265,10 +930,14 @@ impl Session {
             .build();
 
         let request_op = || async {
+            let builder = match req {
+                Some(req) => self.client.post(query_url).json(req),
+                None => self.client.get(query_url),
+            };
             let json = self
-                .client
-                .post(&query_url)
-                .json(&req)
+                .tokens
+                .authorize(builder)
+                .await
                 .build()
                 .map_err(|e| SnowflakeError::ExecutionError(e.into(), None))
                 .map_err(backoff::Error::Permanent)?;
@@ -299,30 +968,10 @@ impl Session {
         })
         .await?;
 
-        let res: DataResponse<serde_json::Value> = serde_json::from_str(&text).map_err(|e| {
+        serde_json::from_str(&text).map_err(|e| {
             log::error!("Failed to execute query {query} with URL {query_url} due to deserialization error.");
             SnowflakeError::new_deserialization_error_with_value(e.into(), text)
-        })?;
-
-        if !res.success {
-            let err = ErrorResult::deserialize(res.data, self)?;
-            if let Some(message) = res.message {
-                return Err(SnowflakeError::ExecutionError(
-                    anyhow!(message),
-                    Some(err),
-                ));
-            }
-            else {
-                return Err(SnowflakeError::ExecutionError(
-                    anyhow!("No reason given by Snowflake API"),
-                    Some(err),
-                ));
-            }
-        }
-
-        let parsed: T = serde_json::from_value(res.data.clone())
-            .map_err(|e| SnowflakeError::new_deserialization_error_with_value(e.into(), res.data.to_string()))?;
-        Ok(parsed)
+        })
     }
 
     fn parse_response_status_with_retry(
@@ -335,10 +984,7 @@ impl Session {
             return Ok(());
         }
 
-        let query_detail_url = match &query_id {
-            Some(id) => get_query_detail_url(self, id),
-            None => "".to_owned(),
-        };
+        let query_detail_url = query_id.as_deref().and_then(|id| get_query_detail_url(self, id));
         let query_id_str = query_id.unwrap_or_default();
 
         let err = SnowflakeError::ExecutionError(
@@ -361,17 +1007,97 @@ impl Session {
         return Err(backoff::Error::Permanent(err));
     }
 
-    fn get_queries_url(&self, command: &str) -> String {
+    /// Rolls back the current transaction without waiting for it. The next query of the session waits for
+    /// the rollback to finish first.
+    pub(crate) fn rollback_in_background(&self) {
+        self.in_transaction.store(false, Ordering::SeqCst);
+
+        let handle = match tokio::runtime::Handle::try_current() {
+            Ok(handle) => handle,
+            Err(_) => {
+                log::warn!("Transaction dropped outside of a tokio runtime, it will not be rolled back.");
+                return;
+            }
+        };
+
+        let req = QueryRequest {
+            async_exec: false,
+            parameters: None,
+            query_submission_time: Utc::now().timestamp_millis(),
+            sequence_id: self.sequence_counter.fetch_add(1, Ordering::Relaxed),
+            sql_text: "ROLLBACK",
+            bindings: None,
+        };
+        let body = match serde_json::to_value(req) {
+            Ok(body) => body,
+            Err(e) => {
+                log::warn!("Failed to roll back dropped transaction with error: {e}");
+                return;
+            }
+        };
+
+        let client = self.client.clone();
+        let url = self.get_queries_url("query-request", &uuid::Uuid::new_v4().to_string());
+        let tokens = self.tokens.clone();
+        let rollback = handle.spawn(async move {
+            if let Err(e) = post_request(&client, &url, &tokens, &body).await {
+                log::warn!("Failed to roll back dropped transaction with error: {e}");
+            }
+        });
+        *self.pending_rollback.lock().unwrap() = Some(rollback);
+    }
+
+    /// Aborts the request with `request_id` if the returned guard is dropped before being disarmed.
+    fn abort_on_drop(&self, request_id: &str, sql_text: &str) -> AbortOnDrop {
+        let abort = self.cancel_on_drop.then(|| {
+            let url = self.get_queries_url("abort-request", &uuid::Uuid::new_v4().to_string());
+            let submitted = SubmittedQuery {
+                request_id: request_id.to_owned(),
+                sql_text: sql_text.to_owned(),
+            };
+            (self.client.clone(), url, self.tokens.clone(), submitted)
+        });
+        AbortOnDrop(abort)
+    }
+
+    fn get_session_url(&self, command: &str) -> String {
         let uuid = uuid::Uuid::new_v4();
+        let guid = uuid::Uuid::new_v4();
+        let url = format!("{}/session/{command}?requestId={uuid}&request_guid={guid}", self.host);
+        log::debug!("Using session url {url}");
+        url
+    }
+
+    fn get_session_delete_url(&self) -> String {
+        let uuid = uuid::Uuid::new_v4();
+        let guid = uuid::Uuid::new_v4();
+        let url = format!("{}/session?delete=true&requestId={uuid}&request_guid={guid}", self.host);
+        log::debug!("Using session url {url}");
+        url
+    }
+
+    fn get_queries_url(&self, command: &str, request_id: &str) -> String {
         let guid = uuid::Uuid::new_v4();
         let url = format!(
-            "{}/queries/v1/{command}?requestId={uuid}&request_guid={guid}",
+            "{}/queries/v1/{command}?requestId={request_id}&request_guid={guid}",
             self.host
         );
         log::debug!("Using query url {url}");
         url
     }
 
+    fn get_query_result_url(&self, query_id: &str) -> String {
+        let encoded_query_id = url_escape::encode_fragment(query_id);
+        let uuid = uuid::Uuid::new_v4();
+        let guid = uuid::Uuid::new_v4();
+        let url = format!(
+            "{}/queries/{encoded_query_id}/result?requestId={uuid}&request_guid={guid}",
+            self.host
+        );
+        log::debug!("Using query result url {url}");
+        url
+    }
+
     fn get_monitoring_queries_url(&self, query_id: &String) -> String {
         let encoded_query_id = url_escape::encode_fragment(query_id);
         let url = format!("{}/monitoring/queries/{encoded_query_id}", self.host);
@@ -393,3 +1119,197 @@ impl Session {
             .map(|d| d.checked_sub(start_ts.elapsed()).unwrap_or_default())
     }
 }
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        if let Some(heartbeat) = self.heartbeat.take() {
+            heartbeat.abort();
+        }
+
+        if self.close_on_drop {
+            match tokio::runtime::Handle::try_current() {
+                Ok(handle) => {
+                    let client = self.client.clone();
+                    let url = self.get_session_delete_url();
+                    let tokens = self.tokens.clone();
+                    handle.spawn(async move {
+                        if let Err(e) = delete_session(&client, &url, &tokens).await {
+                            log::warn!("Failed to close dropped session with error: {e}");
+                        }
+                    });
+                }
+                Err(_) => log::warn!("Session dropped outside of a tokio runtime, it will not be closed."),
+            }
+        }
+    }
+}
+
+/// Guard aborting a query on the server unless it is disarmed once the query finished.
+struct AbortOnDrop(Option<(reqwest::Client, String, TokenManager, SubmittedQuery)>);
+
+impl AbortOnDrop {
+    fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        if let Some((client, url, tokens, submitted)) = self.0.take() {
+            match tokio::runtime::Handle::try_current() {
+                Ok(handle) => {
+                    handle.spawn(async move {
+                        if let Err(e) = abort_request(&client, &url, &tokens, &submitted).await {
+                            log::warn!("Failed to abort dropped query with error: {e}");
+                        }
+                    });
+                }
+                Err(_) => log::warn!("Query dropped outside of a tokio runtime, it will not be aborted."),
+            }
+        }
+    }
+}
+
+async fn heartbeat(client: reqwest::Client, url: String, tokens: TokenManager, interval: Duration) {
+    let mut ticker = tokio::time::interval_at(Instant::now() + interval, interval);
+    loop {
+        ticker.tick().await;
+        match post_request(&client, &url, &tokens, &json!({})).await {
+            Ok(_) => log::debug!("Session heartbeat sent."),
+            Err(e) => log::warn!("Session heartbeat failed with error: {e}"),
+        }
+    }
+}
+
+async fn delete_session(client: &reqwest::Client, url: &str, tokens: &TokenManager) -> Result<(), SnowflakeError> {
+    match post_request(client, url, tokens, &json!({})).await {
+        Err(SnowflakeError::SessionError(_, Some(code))) if code == SESSION_GONE_CODE => {
+            log::debug!("Session was already closed.");
+            Ok(())
+        }
+        Err(e) => Err(e),
+        Ok(_) => {
+            log::debug!("Session closed.");
+            Ok(())
+        }
+    }
+}
+
+/// Aborts a running query.
+async fn abort_request(
+    client: &reqwest::Client,
+    url: &str,
+    tokens: &TokenManager,
+    submitted: &SubmittedQuery,
+) -> Result<(), SnowflakeError> {
+    let req = AbortRequest {
+        sql_text: &submitted.sql_text,
+        request_id: &submitted.request_id,
+    };
+    let body = serde_json::to_value(req).map_err(|e| SnowflakeError::SerializationError(e.into()))?;
+
+    match post_request(client, url, tokens, &body).await {
+        Err(SnowflakeError::SessionError(e, _)) => Err(SnowflakeError::ExecutionError(e, None)),
+        Err(e) => Err(e),
+        Ok(_) => {
+            log::debug!("Aborted request '{}'.", submitted.request_id);
+            Ok(())
+        }
+    }
+}
+
+/// Sends a request to the Snowflake API outside of query execution, renewing the session token once if it expired.
+async fn post_request(
+    client: &reqwest::Client,
+    url: &str,
+    tokens: &TokenManager,
+    body: &serde_json::Value,
+) -> Result<DataResponse<serde_json::Value>, SnowflakeError> {
+    let send = || async {
+        let body = tokens
+            .authorize(client.post(url))
+            .await
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| SnowflakeError::SessionError(e.into(), None))?;
+
+        let text = body
+            .text()
+            .await
+            .map_err(|e| SnowflakeError::SessionError(e.into(), None))?;
+        serde_json::from_str::<DataResponse<serde_json::Value>>(&text)
+            .map_err(|e| SnowflakeError::new_deserialization_error_with_value(e.into(), text))
+    };
+
+    let token = tokens.session_token().await;
+    let mut res = send().await?;
+
+    if res.code.as_deref() == Some(SESSION_TOKEN_EXPIRED_CODE) {
+        if let Some(token) = token {
+            tokens.renew(&token).await?;
+            res = send().await?;
+        }
+    }
+
+    if !res.success {
+        let message = res
+            .message
+            .unwrap_or_else(|| "No reason given by Snowflake API".to_owned());
+        return Err(SnowflakeError::SessionError(anyhow!(message), res.code));
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_update_context() {
+        let mut context = SessionContext::default();
+        let mut internal: InternalResult = serde_json::from_value(json!({
+            "rowtype": [],
+            "queryId": "01",
+            "total": 0,
+            "queryResultFormat": "json",
+            "finalDatabaseName": "DB",
+            "finalSchemaName": "PUBLIC",
+            "finalRoleName": "SYSADMIN",
+            "parameters": [{"name": "TIMEZONE", "value": "Etc/GMT"}],
+        }))
+        .unwrap();
+        context.update(&internal);
+        assert_eq!(context.database.as_deref(), Some("DB"));
+        assert_eq!(context.role.as_deref(), Some("SYSADMIN"));
+        assert_eq!(context.warehouse, None);
+        assert_eq!(context.parameters["TIMEZONE"], json!("Etc/GMT"));
+
+        internal.final_database_name = Some("OTHER".to_owned());
+        internal.final_role_name = None;
+        internal.parameters = None;
+        context.update(&internal);
+        assert_eq!(context.database.as_deref(), Some("OTHER"));
+        assert_eq!(context.role.as_deref(), Some("SYSADMIN"));
+        assert_eq!(context.parameters.len(), 1);
+    }
+
+    #[test]
+    fn test_parameter_statement() {
+        assert_eq!(Session::parameter_name("query_tag").unwrap(), "QUERY_TAG");
+        assert!(Session::parameter_name("QUERY_TAG = 'x'; DROP TABLE t").is_err());
+        assert!(Session::parameter_name("").is_err());
+
+        assert_eq!(Session::parameter_literal(&json!("it's")).unwrap(), r"'it\'s'");
+        assert_eq!(Session::parameter_literal(&json!(r"a\b")).unwrap(), r"'a\\b'");
+        assert_eq!(Session::parameter_literal(&json!(3600)).unwrap(), "3600");
+        assert_eq!(Session::parameter_literal(&json!(true)).unwrap(), "true");
+        assert!(Session::parameter_literal(&json!([1])).is_err());
+
+        assert_eq!(Session::parameter_value("4", "NUMBER"), json!(4));
+        assert_eq!(Session::parameter_value("1.5", "NUMBER"), json!(1.5));
+        assert_eq!(Session::parameter_value("true", "BOOLEAN"), json!(true));
+        assert_eq!(Session::parameter_value("Etc/GMT", "STRING"), json!("Etc/GMT"));
+    }
+}
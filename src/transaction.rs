@@ -0,0 +1,194 @@
+use crate::bindings::Params;
+use crate::errors::SnowflakeError;
+use crate::options::ExecuteOptions;
+use crate::responses::result::vec::VecResult;
+use crate::responses::QueryResult;
+use crate::session::Session;
+
+use anyhow::anyhow;
+use std::sync::atomic::Ordering;
+
+/// Transaction started with [`Session::begin`].
+///
+/// Ends with [`Transaction::commit`] or [`Transaction::rollback`], both consuming the guard. A transaction
+/// dropped without either is rolled back in the background before the next query of the session runs.
+///
+/// Whether the transaction is still open is tracked from the responses of the session, so a DDL statement,
+/// which commits the transaction in Snowflake, ends it as well.
+#[derive(Debug)]
+pub struct Transaction<'a> {
+    session: &'a Session,
+    active: bool,
+}
+
+impl<'a> Transaction<'a> {
+    pub(crate) async fn begin(session: &'a Session) -> Result<Transaction<'a>, SnowflakeError> {
+        if session.in_transaction() {
+            return Err(SnowflakeError::TransactionError(anyhow!(
+                "A transaction is already active on this session"
+            )));
+        }
+
+        session.execute::<VecResult>("BEGIN").await?;
+        Ok(Transaction { session, active: true })
+    }
+
+    pub async fn execute<T: QueryResult + Send + Sync>(&self, query: &str) -> Result<T, SnowflakeError> {
+        Self::check_statement(query)?;
+        self.session.execute(query).await
+    }
+
+    pub async fn execute_with_params<T: QueryResult + Send + Sync>(
+        &self,
+        query: &str,
+        params: impl Into<Params>,
+    ) -> Result<T, SnowflakeError> {
+        Self::check_statement(query)?;
+        self.session.execute_with_params(query, params).await
+    }
+
+    pub async fn execute_with_options<T: QueryResult + Send + Sync>(
+        &self,
+        query: &str,
+        options: ExecuteOptions,
+    ) -> Result<T, SnowflakeError> {
+        Self::check_statement(query)?;
+        self.session.execute_with_options(query, options).await
+    }
+
+    pub async fn commit(mut self) -> Result<(), SnowflakeError> {
+        self.end("COMMIT").await
+    }
+
+    pub async fn rollback(mut self) -> Result<(), SnowflakeError> {
+        self.end("ROLLBACK").await
+    }
+
+    async fn end(&mut self, statement: &str) -> Result<(), SnowflakeError> {
+        if !self.session.in_transaction() {
+            self.active = false;
+            return Err(SnowflakeError::TransactionError(anyhow!(
+                "The transaction was already ended, e.g. by a DDL statement, which commits it"
+            )));
+        }
+
+        // Stays active if the statement fails, so the transaction is rolled back once dropped.
+        self.session.execute::<VecResult>(statement).await?;
+        self.active = false;
+        self.session.in_transaction.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Rejects statements which would start or end a transaction behind the guard's back.
+    fn check_statement(query: &str) -> Result<(), SnowflakeError> {
+        match Self::opens_transaction(query) {
+            Some(true) => Err(SnowflakeError::TransactionError(anyhow!(
+                "Nested transactions are not supported"
+            ))),
+            Some(false) => Err(SnowflakeError::TransactionError(anyhow!(
+                "Use Transaction::commit or Transaction::rollback to end the transaction"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns whether `query` starts (`Some(true)`) or ends (`Some(false)`) a transaction, or `None` if it is
+    /// not a transaction control statement.
+    ///
+    /// `BEGIN` also opens Snowflake Scripting blocks, so it only counts when followed by the end of the
+    /// statement, `TRANSACTION`, `WORK` or `NAME`.
+    pub(crate) fn opens_transaction(query: &str) -> Option<bool> {
+        let query = Self::skip_comments(query);
+        let (keyword, rest) = query.split_at(query.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(query.len()));
+
+        match keyword.to_ascii_uppercase().as_str() {
+            "BEGIN" => {
+                let rest = Self::skip_comments(rest);
+                let next = rest
+                    .split(|c: char| !c.is_ascii_alphabetic())
+                    .next()
+                    .unwrap_or_default()
+                    .to_ascii_uppercase();
+                let transaction = rest.is_empty()
+                    || rest.starts_with(';')
+                    || matches!(next.as_str(), "TRANSACTION" | "WORK" | "NAME");
+                transaction.then_some(true)
+            }
+            "START" => Some(true),
+            "COMMIT" | "ROLLBACK" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Skips leading whitespace, comments and parentheses.
+    fn skip_comments(mut query: &str) -> &str {
+        loop {
+            query = query.trim_start_matches(|c: char| c.is_whitespace() || c == '(');
+            query = if let Some(rest) = query.strip_prefix("--").or_else(|| query.strip_prefix("//")) {
+                rest.split_once('\n').map_or("", |(_, rest)| rest)
+            }
+            else if let Some(rest) = query.strip_prefix("/*") {
+                rest.split_once("*/").map_or("", |(_, rest)| rest)
+            }
+            else {
+                return query;
+            };
+        }
+    }
+}
+
+impl Drop for Transaction<'_> {
+    fn drop(&mut self) {
+        if self.active && self.session.in_transaction() {
+            log::debug!("Transaction dropped without commit, rolling back.");
+            self.session.rollback_in_background();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_check_statement() {
+        Transaction::check_statement("INSERT INTO t VALUES (1)").expect("Statement should be allowed");
+        Transaction::check_statement("  select 1").expect("Statement should be allowed");
+        Transaction::check_statement("/* commit */ SELECT 1").expect("Statement should be allowed");
+        Transaction::check_statement("BEGIN SELECT 1; END;").expect("Statement should be allowed");
+        Transaction::check_statement("begin\n  insert into t values (1);\nend").expect("Statement should be allowed");
+        Transaction::check_statement("BEGIN -- work\n  LET x := 1; RETURN x; END")
+            .expect("Statement should be allowed");
+
+        for query in [
+            "BEGIN",
+            "begin transaction",
+            "START TRANSACTION",
+            "commit;",
+            " Rollback",
+            "/* x */ COMMIT",
+            "-- x\n--y\n  commit",
+            "(COMMIT)",
+            "begin;commit",
+            "BEGIN WORK",
+            "begin name t1",
+            "BEGIN /* x */ ;",
+        ] {
+            match Transaction::check_statement(query) {
+                Err(SnowflakeError::TransactionError(_)) => {}
+                res => panic!("Unexpected result {res:?} for {query}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_opens_transaction() {
+        assert_eq!(Transaction::opens_transaction("BEGIN"), Some(true));
+        assert_eq!(Transaction::opens_transaction("start transaction"), Some(true));
+        assert_eq!(Transaction::opens_transaction("COMMIT WORK"), Some(false));
+        assert_eq!(Transaction::opens_transaction("rollback;"), Some(false));
+        assert_eq!(Transaction::opens_transaction("BEGIN SELECT 1; END;"), None);
+        assert_eq!(Transaction::opens_transaction("SELECT CURRENT_TRANSACTION()"), None);
+    }
+}
@@ -1,3 +1,6 @@
+use crate::errors::SnowflakeError;
+
+use anyhow::anyhow;
 use percent_encoding::percent_decode_str;
 
 pub(crate) fn urldecode_some(s: Option<&str>) -> String {
@@ -13,6 +16,12 @@ pub(crate) fn urldecode_some(s: Option<&str>) -> String {
     }
 }
 
+/// Proxy for HTTPS requests at `address`.
+pub(crate) fn https_proxy(address: &str) -> Result<reqwest::Proxy, SnowflakeError> {
+    reqwest::Proxy::https(address)
+        .map_err(|e| SnowflakeError::GeneralError(anyhow!(e).context(format!("Invalid proxy '{address}'"))))
+}
+
 #[allow(unused)]
 pub(crate) fn until_err<T, E>(err: &mut &mut Result<(), E>, item: Result<T, E>) -> Option<T> {
     match item {
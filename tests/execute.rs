@@ -1,11 +1,12 @@
 mod support;
 
+use futures::StreamExt;
 use std::time::Duration;
 
 use snowstorm::errors::SnowflakeError;
 use snowstorm::responses::{
     result::{hashmap::HashMapResult, jsonmap::JsonMapResult, jsonvec::JsonVecResult, vec::VecResult},
-    types::value::Value,
+    types::{query::QueryStatus, value::Value},
 };
 use support::{common_init, new_full_client, new_valid_client};
 
@@ -128,6 +129,84 @@ async fn execute_select_into_chunked_success() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+#[tokio::test]
+async fn execute_stream_chunked_success() -> Result<(), anyhow::Error> {
+    common_init();
+
+    let client = new_full_client()
+        .expect("Client should have been created")
+        .max_parallel_downloads(4);
+    let session = client.connect().await.expect("Session should have been created");
+    let stream = session
+        .execute_stream::<VecResult>("SELECT * FROM LUABASE.CLICKHOUSE.ETHEREUM_TRANSACTIONS LIMIT 2000")
+        .await
+        .unwrap();
+
+    let mut rows = 0;
+    futures::pin_mut!(stream);
+    while let Some(batch) = stream.next().await {
+        rows += batch.expect("Chunk should have been downloaded").len();
+    }
+    assert_eq!(rows, 2000);
+    Ok(())
+}
+
+#[tokio::test]
+async fn execute_multi_success() -> Result<(), anyhow::Error> {
+    common_init();
+
+    let client = new_valid_client();
+    let session = client.connect().await.expect("Session should have been created");
+    let results = session
+        .execute_multi::<VecResult>("SELECT 1; SELECT 2, 3; SHOW GRANTS")
+        .await
+        .expect("Results should have been returned");
+
+    assert_eq!(results.len(), 3);
+    for statement in &results {
+        assert_eq!(statement.status, QueryStatus::Success);
+    }
+    let second = results[1].result.as_ref().expect("Statement should have succeeded");
+    assert_eq!(second.rowtype.len(), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn transaction_success() -> Result<(), anyhow::Error> {
+    common_init();
+
+    let client = new_full_client().expect("Client should have been created");
+    let session = client.connect().await.expect("Session should have been created");
+    session
+        .execute::<VecResult>("CREATE TEMPORARY TABLE SNOWSTORM_TRANSACTION_TEST (ID INT)")
+        .await
+        .unwrap();
+
+    let tx = session.begin().await.expect("Transaction should have started");
+    assert_err!(session.begin().await, Err(SnowflakeError::TransactionError(_)));
+    assert_err!(
+        tx.execute::<VecResult>("COMMIT").await,
+        Err(SnowflakeError::TransactionError(_))
+    );
+    tx.execute::<VecResult>("INSERT INTO SNOWSTORM_TRANSACTION_TEST VALUES (1)")
+        .await
+        .unwrap();
+    drop(tx);
+
+    let tx = session.begin().await.expect("Transaction should have started");
+    tx.execute::<VecResult>("INSERT INTO SNOWSTORM_TRANSACTION_TEST VALUES (2)")
+        .await
+        .unwrap();
+    tx.commit().await.expect("Transaction should have been committed");
+
+    let res = session
+        .execute::<VecResult>("SELECT ID FROM SNOWSTORM_TRANSACTION_TEST")
+        .await
+        .unwrap();
+    assert_eq!(res.rowset.len(), 1);
+    Ok(())
+}
+
 #[tokio::test]
 async fn execute_error() -> Result<(), anyhow::Error> {
     common_init();
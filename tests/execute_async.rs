@@ -2,6 +2,7 @@ mod support;
 
 use snowstorm::errors::SnowflakeError;
 use snowstorm::responses::result::vec::VecResult;
+use snowstorm::responses::types::query::QueryStatus;
 use support::{common_init, new_full_client, new_valid_client};
 
 #[tokio::test]
@@ -55,3 +56,69 @@ async fn execute_async_select_into_vec_success() -> Result<(), anyhow::Error> {
     assert_eq!(res.rowset.len(), res.total);
     Ok(())
 }
+
+#[tokio::test]
+async fn submit_and_fetch_result_success() -> Result<(), anyhow::Error> {
+    common_init();
+
+    let client = new_full_client().expect("Client should have been created");
+    let session = client.connect().await.expect("Session should have been created");
+    let handle = session
+        .submit("SELECT SYSTEM$WAIT(2)")
+        .await
+        .expect("Query should have been submitted");
+
+    let status = session
+        .query_status(&handle.query_id)
+        .await
+        .expect("Status should have been returned");
+    assert!(status.is_still_running() || status == QueryStatus::Success);
+
+    // Results can be fetched from any session of the same user.
+    let other_session = client.connect().await.expect("Session should have been created");
+    let res = other_session
+        .fetch_result::<VecResult>(&handle.query_id)
+        .await
+        .expect("Result should have been returned");
+    assert!(!res.query_id.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn cancel_success() -> Result<(), anyhow::Error> {
+    common_init();
+
+    let client = new_full_client().expect("Client should have been created");
+    let session = client.connect().await.expect("Session should have been created");
+    let handle = session
+        .submit("SELECT SYSTEM$WAIT(60)")
+        .await
+        .expect("Query should have been submitted");
+
+    session
+        .cancel(&handle.query_id)
+        .await
+        .expect("Query should have been cancelled");
+    let res = session.fetch_result::<VecResult>(&handle.query_id).await;
+    assert_err!(res, Err(SnowflakeError::ExecutionError(_, _)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn execute_async_matches_execute() -> Result<(), anyhow::Error> {
+    common_init();
+
+    let client = new_full_client().expect("Client should have been created");
+    let session = client.connect().await.expect("Session should have been created");
+    let query = "SELECT * FROM SNOWSTORM_TEST_DATA.PUBLIC.TEST";
+    let res = session.execute::<VecResult>(query).await.unwrap();
+    let async_res = session.execute_async::<VecResult>(query).await.unwrap();
+
+    assert_eq!(async_res.total, res.total);
+    assert_eq!(async_res.rowset.len(), res.rowset.len());
+    assert_eq!(
+        format!("{:?}", async_res.rowtype),
+        format!("{:?}", res.rowtype)
+    );
+    Ok(())
+}
@@ -0,0 +1,242 @@
+#![cfg(feature = "mock")]
+
+mod support;
+
+use serde_json::json;
+use snowstorm::errors::SnowflakeError;
+use snowstorm::mock::{MockColumn, MockResult, MockServer};
+use snowstorm::options::ExecuteOptions;
+use snowstorm::responses::result::{hashmap::HashMapResult, vec::VecResult};
+use snowstorm::responses::types::value::Value;
+use snowstorm::session::Session;
+use snowstorm::Snowstorm;
+use std::path::PathBuf;
+use support::{common_init, new_valid_client};
+
+const TYPES_QUERY: &str =
+    "SELECT column1::NUMBER(38, 0) AS ID, column2::VARCHAR AS NAME, column3::NUMBER(10, 2) AS PRICE, \
+    column4::BOOLEAN AS ACTIVE, column5::DATE AS CREATED_ON, column6::TIMESTAMP_NTZ(3) AS UPDATED_AT \
+    FROM VALUES (1, 'alice', 12.50, TRUE, '2023-01-15', '2023-01-15 10:30:00.125'), \
+    (2, NULL, 0.99, FALSE, '2023-02-01', NULL), (3, 'carol', NULL, NULL, NULL, '2023-03-31 23:59:59.999')";
+const SEQUENCE_QUERY: &str = "SELECT ROW_NUMBER() OVER (ORDER BY SEQ4()) AS ID, 'row_' || ID AS NAME \
+    FROM TABLE(GENERATOR(ROWCOUNT => 3000)) ORDER BY ID";
+
+fn fixture_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("snowstorm-{name}-{}", std::process::id()));
+    _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[tokio::test]
+async fn record_and_replay_success() -> Result<(), anyhow::Error> {
+    let dir = fixture_dir("record_and_replay");
+    let query = "SELECT * FROM users";
+
+    let server = MockServer::start().await?;
+    server.on_query(
+        query,
+        MockResult::new(vec![MockColumn::fixed("ID"), MockColumn::text("NAME")])
+            .row(&[Some("1"), Some("alice")])
+            .row(&[Some("2"), None])
+            .chunk_size(1),
+    );
+
+    let session = server.snowstorm().record_fixtures(&dir).connect().await?;
+    let recorded: HashMapResult = session.execute(query).await?;
+    let handle = session.submit(query).await?;
+    let fetched: VecResult = session.fetch_result(&handle.query_id).await?;
+    drop(session);
+    drop(server);
+
+    let fixtures = std::fs::read_dir(&dir)?
+        .map(|entry| std::fs::read_to_string(entry?.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert!(fixtures.iter().any(|f| f.contains("/chunks/0")));
+    assert!(fixtures.iter().all(|f| !f.contains("mock-session-token")));
+
+    let client = Snowstorm::new("mock".to_owned(), "mock".to_owned(), "mock".to_owned()).replay_fixtures(&dir);
+    let session = client.connect().await?;
+    let replayed: HashMapResult = session.execute(query).await?;
+    assert_eq!(replayed.query_id, recorded.query_id);
+    assert_eq!(replayed.rowset.len(), 2);
+    assert_eq!(replayed.rowset[0]["NAME"].to_string(), "alice");
+
+    let res: VecResult = session.fetch_result(&handle.query_id).await?;
+    assert_eq!(res.rowset.len(), fetched.rowset.len());
+
+    _ = std::fs::remove_dir_all(&dir);
+    Ok(())
+}
+
+#[tokio::test]
+async fn record_stage_credentials_redacted_success() -> Result<(), anyhow::Error> {
+    let dir = fixture_dir("record_stage");
+    let query = "PUT file:///tmp/users.csv @~";
+    let secrets = [
+        "mock-key-id",
+        "mock-secret-key",
+        "mock-aws-token",
+        "mock-sas-token",
+        "mock-gcs-token",
+        "https://mock-presigned-url",
+        "mock-master-key",
+    ];
+
+    let server = MockServer::start().await?;
+    server.on_query(
+        query,
+        MockResult::new(vec![MockColumn::text("source"), MockColumn::text("status")])
+            .row(&[Some("users.csv"), Some("UPLOADED")])
+            .statement_type_id(0x7000)
+            .stage_info(json!({
+                "locationType": "S3",
+                "location": "stage/path/",
+                "creds": {
+                    "AWS_KEY_ID": secrets[0],
+                    "AWS_SECRET_KEY": secrets[1],
+                    "AWS_TOKEN": secrets[2],
+                    "AZURE_SAS_TOKEN": secrets[3],
+                    "GCS_ACCESS_TOKEN": secrets[4],
+                },
+                "presignedUrl": secrets[5],
+                "encryptionMaterial": { "queryStageMasterKey": secrets[6], "queryId": "01", "smkId": 1 },
+            })),
+    );
+
+    let session = server.snowstorm().record_fixtures(&dir).connect().await?;
+    session.execute::<VecResult>(query).await?;
+    drop(session);
+    drop(server);
+
+    let fixtures = std::fs::read_dir(&dir)?
+        .map(|entry| std::fs::read_to_string(entry?.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let stage = fixtures
+        .iter()
+        .find(|f| f.contains("stageInfo"))
+        .expect("Stage response should have been recorded");
+    assert!(stage.contains("stage/path/"));
+    for secret in secrets {
+        assert!(!stage.contains(secret), "{secret} should have been redacted");
+    }
+
+    _ = std::fs::remove_dir_all(&dir);
+    Ok(())
+}
+
+/// Connects to the hand-written fixtures under `tests/data/fixtures/{name}`.
+async fn replay(name: &str) -> Result<Session, SnowflakeError> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data/fixtures")
+        .join(name);
+    Snowstorm::new("mock".to_owned(), "mock".to_owned(), "mock".to_owned())
+        .replay_fixtures(dir)
+        .connect()
+        .await
+}
+
+fn json_format() -> ExecuteOptions {
+    ExecuteOptions::new().parameter("PYTHON_CONNECTOR_QUERY_RESULT_FORMAT", "JSON")
+}
+
+fn assert_types_rowset(rowset: &[Vec<Value>]) {
+    assert_eq!(rowset.len(), 3);
+    assert!(matches!(&rowset[0][0], Value::Nullable(Some(v)) if matches!(**v, Value::I128(1))));
+    assert_eq!(rowset[0][1].to_string(), "alice");
+    assert!(matches!(&rowset[1][1], Value::Nullable(None)));
+    assert!(matches!(&rowset[0][3], Value::Nullable(Some(v)) if matches!(**v, Value::Boolean(true))));
+    assert!(matches!(&rowset[1][3], Value::Nullable(Some(v)) if matches!(**v, Value::Boolean(false))));
+    assert!(matches!(&rowset[2][3], Value::Nullable(None)));
+    assert_eq!(rowset[0][4].to_string(), "2023-01-15");
+    assert!(matches!(&rowset[2][4], Value::Nullable(None)));
+    assert_eq!(rowset[0][5].to_string(), "2023-01-15T10:30:00.125");
+    // JSON timestamps are parsed as floats, precise to the microsecond.
+    assert!(rowset[2][5].to_string().starts_with("2023-03-31T23:59:59.999"));
+}
+
+fn assert_sequence_rowset(rowset: &[Vec<Value>]) {
+    assert_eq!(rowset.len(), 3000);
+    for (i, row) in rowset.iter().enumerate() {
+        assert!(matches!(row[0], Value::I64(id) if id == i as i64 + 1));
+        assert_eq!(row[1].to_string(), format!("row_{}", i + 1));
+    }
+}
+
+#[tokio::test]
+async fn replay_json_success() -> Result<(), anyhow::Error> {
+    let session = replay("json").await?;
+    let res: VecResult = session.execute_with_options(TYPES_QUERY, json_format()).await?;
+    assert_eq!(res.total, 3);
+    assert_types_rowset(&res.rowset);
+
+    let res: HashMapResult = session.execute_with_options(TYPES_QUERY, json_format()).await?;
+    assert_eq!(res.rowset[2]["NAME"].to_string(), "carol");
+    Ok(())
+}
+
+#[tokio::test]
+async fn replay_json_chunked_success() -> Result<(), anyhow::Error> {
+    let session = replay("json_chunked").await?;
+    let res: VecResult = session.execute_with_options(SEQUENCE_QUERY, json_format()).await?;
+    assert_eq!(res.total, 3000);
+    assert_sequence_rowset(&res.rowset);
+    Ok(())
+}
+
+#[cfg(feature = "arrow")]
+#[tokio::test]
+async fn replay_arrow_success() -> Result<(), anyhow::Error> {
+    let session = replay("arrow").await?;
+    let res: VecResult = session.execute(TYPES_QUERY).await?;
+    assert_types_rowset(&res.rowset);
+    let price: decimal_rs::Decimal = "12.50".parse().unwrap();
+    assert!(matches!(&res.rowset[0][2], Value::Nullable(Some(v)) if matches!(**v, Value::Decimal(d) if d == price)));
+    assert!(matches!(&res.rowset[2][2], Value::Nullable(None)));
+    Ok(())
+}
+
+#[cfg(feature = "arrow")]
+#[tokio::test]
+async fn replay_arrow_chunked_success() -> Result<(), anyhow::Error> {
+    let session = replay("arrow_chunked").await?;
+    let res: VecResult = session.execute(SEQUENCE_QUERY).await?;
+    assert_sequence_rowset(&res.rowset);
+
+    let stream = session.execute_stream::<VecResult>(SEQUENCE_QUERY).await?;
+    futures::pin_mut!(stream);
+    let mut rows = 0;
+    while let Some(batch) = futures::StreamExt::next(&mut stream).await {
+        rows += batch?.len();
+    }
+    assert_eq!(rows, 3000);
+    Ok(())
+}
+
+/// Records the fixtures under `tests/data/fixtures` from the account configured in `.env`.
+#[tokio::test]
+#[ignore = "records fixtures from a Snowflake account"]
+async fn record_snowflake_fixtures() -> Result<(), anyhow::Error> {
+    common_init();
+
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/data/fixtures");
+    for (name, query, options) in [
+        ("json", TYPES_QUERY, json_format()),
+        ("json_chunked", SEQUENCE_QUERY, json_format()),
+        ("arrow", TYPES_QUERY, ExecuteOptions::new()),
+        ("arrow_chunked", SEQUENCE_QUERY, ExecuteOptions::new()),
+    ] {
+        let dir = root.join(name);
+        _ = std::fs::remove_dir_all(&dir);
+        let session = new_valid_client().record_fixtures(&dir).connect().await?;
+        session.execute_with_options::<VecResult>(query, options).await?;
+        drop(session);
+
+        if name.ends_with("chunked") {
+            assert!(
+                dir.join("0003.json").exists(),
+                "Result of {name} should have been chunked"
+            );
+        }
+    }
+    Ok(())
+}
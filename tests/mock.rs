@@ -0,0 +1,186 @@
+#![cfg(feature = "mock")]
+
+use snowstorm::bindings::ARRAY_BINDING_THRESHOLD;
+use snowstorm::errors::SnowflakeError;
+use snowstorm::mock::{MockColumn, MockResult, MockServer};
+use snowstorm::options::ExecuteOptions;
+use snowstorm::responses::result::{hashmap::HashMapResult, vec::VecResult};
+use snowstorm::responses::types::{query::QueryStatus, stats::StatementType, value::Value};
+
+fn users() -> MockResult {
+    MockResult::new(vec![MockColumn::fixed("ID"), MockColumn::text("NAME")])
+        .row(&[Some("1"), Some("alice")])
+        .row(&[Some("2"), None])
+        .row(&[Some("3"), Some("carol")])
+}
+
+#[tokio::test]
+async fn mock_execute_success() -> Result<(), anyhow::Error> {
+    let server = MockServer::start().await?;
+    server.on_query("SELECT * FROM users", users().chunk_size(1));
+
+    let session = server.snowstorm().connect().await?;
+    let res: HashMapResult = session.execute("SELECT * FROM users").await?;
+    assert_eq!(res.total, 3);
+    assert_eq!(res.rowset.len(), 3);
+    assert_eq!(res.statement_type, StatementType::Select);
+    assert_eq!(res.rowset[2]["NAME"].to_string(), "carol");
+    assert_eq!(session.current_role().as_deref(), Some("PUBLIC"));
+
+    let res: VecResult = session.execute("USE SCHEMA public").await?;
+    assert_eq!(res.rowset.len(), 1);
+    Ok(())
+}
+
+#[cfg(feature = "arrow")]
+#[tokio::test]
+async fn mock_execute_arrow_success() -> Result<(), anyhow::Error> {
+    let server = MockServer::start().await?;
+    server.on_query("SELECT * FROM users", users().chunk_size(2).arrow());
+
+    let session = server.snowstorm().connect().await?;
+    let res: VecResult = session.execute("SELECT * FROM users").await?;
+    assert_eq!(res.rowset.len(), 3);
+    assert!(matches!(res.rowset[1][1], Value::Nullable(None)));
+    assert_eq!(res.rowset[2][1].to_string(), "carol");
+    Ok(())
+}
+
+#[tokio::test]
+async fn mock_execute_fail() -> Result<(), anyhow::Error> {
+    let server = MockServer::start().await?;
+    server.on_query("INVALID QUERY", MockResult::error("001003", "SQL compilation error"));
+
+    let session = server.snowstorm().connect().await?;
+    match session.execute::<VecResult>("INVALID QUERY").await {
+        Err(SnowflakeError::ExecutionError(_, Some(err))) => assert_eq!(err.error_code, "001003"),
+        res => panic!("Unexpected result {res:?}"),
+    }
+
+    let handle = session.submit("INVALID QUERY").await?;
+    match session.fetch_result::<VecResult>(&handle.query_id).await {
+        Err(SnowflakeError::ExecutionError(_, Some(err))) => assert_eq!(err.error_code, "001003"),
+        res => panic!("Unexpected result {res:?}"),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn mock_submit_and_fetch_result_success() -> Result<(), anyhow::Error> {
+    let server = MockServer::start().await?;
+    server.on_query("SELECT * FROM users", users());
+
+    let session = server.snowstorm().connect().await?;
+    let handle = session.submit("SELECT * FROM users").await?;
+    let res: VecResult = session.fetch_result(&handle.query_id).await?;
+    assert_eq!(res.query_id, handle.query_id);
+    assert_eq!(res.rowset.len(), 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn mock_execute_with_options_success() -> Result<(), anyhow::Error> {
+    let server = MockServer::start().await?;
+    server.on_query("INSERT INTO users VALUES (?)", MockResult::inserted(1));
+
+    let session = server.snowstorm().connect().await?;
+    let options = ExecuteOptions::new()
+        .query_tag("mock")
+        .params(vec![Value::String("dave".to_owned())]);
+    let res: VecResult = session
+        .execute_with_options("INSERT INTO users VALUES (?)", options)
+        .await?;
+    assert_eq!(res.statement_type, StatementType::Insert);
+    assert_eq!(res.stats.map(|s| s.num_rows_inserted), Some(1));
+
+    let request = server.requests().pop().expect("Request should have been recorded");
+    assert_eq!(request.parameters["QUERY_TAG"], "mock");
+    assert_eq!(request.bindings.unwrap()["1"]["value"], "dave");
+    Ok(())
+}
+
+#[tokio::test]
+async fn mock_query_status_and_cancel_success() -> Result<(), anyhow::Error> {
+    let server = MockServer::start().await?;
+    server.on_query("SELECT * FROM users", users());
+
+    let session = server.snowstorm().connect().await?;
+    let handle = session.submit("SELECT * FROM users").await?;
+    assert_eq!(session.query_status(&handle.query_id).await?, QueryStatus::Success);
+
+    let handle = session.submit("SELECT * FROM users").await?;
+    session.cancel(&handle.query_id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn mock_transaction_ended_by_ddl_success() -> Result<(), anyhow::Error> {
+    let server = MockServer::start().await?;
+    let status = MockResult::new(vec![MockColumn::text("status")]).row(&[Some("Statement executed successfully.")]);
+    server.on_query("BEGIN", status.clone().statement_type_id(0x5000));
+    server.on_query("CREATE TABLE t (id INT)", status.statement_type_id(0x6000));
+
+    let session = server.snowstorm().connect().await?;
+    let tx = session.begin().await?;
+    assert!(session.in_transaction());
+
+    tx.execute::<VecResult>("CREATE TABLE t (id INT)").await?;
+    assert!(!session.in_transaction());
+    drop(tx);
+
+    session.execute::<VecResult>("SELECT 1").await?;
+    assert!(!server.requests().iter().any(|r| r.sql_text == "ROLLBACK"));
+
+    let tx = session.begin().await?;
+    tx.execute::<VecResult>("CREATE TABLE t (id INT)").await?;
+    match tx.commit().await {
+        Err(SnowflakeError::TransactionError(_)) => {}
+        res => panic!("Commit after DDL should have been rejected, got {res:?}"),
+    }
+    assert!(!server.requests().iter().any(|r| r.sql_text == "COMMIT"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn mock_execute_many_in_batches_success() -> Result<(), anyhow::Error> {
+    let server = MockServer::start().await?;
+    let status = MockResult::new(vec![MockColumn::text("status")]).row(&[Some("Statement executed successfully.")]);
+    server.on_query("BEGIN", status.clone().statement_type_id(0x5000));
+    server.on_query("COMMIT", status.statement_type_id(0x5000));
+    let count = ARRAY_BINDING_THRESHOLD / 2 + 1;
+    server.on_query(
+        "INSERT INTO users VALUES (?, ?)",
+        MockResult::inserted(count as u64 / 2),
+    );
+
+    let session = server.snowstorm().connect().await?;
+    assert_eq!(session.execute_many("INSERT INTO users VALUES (?, ?)", &[]).await?, 0);
+    assert!(server.requests().is_empty());
+
+    let mut rows: Vec<_> = (0..count)
+        .map(|i| vec![Value::I64(i as i64), Value::String(format!("user {i}"))])
+        .collect();
+    rows.push(vec![Value::I64(0)]);
+    match session.execute_many("INSERT INTO users VALUES (?, ?)", &rows).await {
+        Err(SnowflakeError::GeneralError(_)) => {}
+        res => panic!("Uneven rows should have been rejected, got {res:?}"),
+    }
+    assert!(server.requests().is_empty());
+
+    // Batches over the threshold are split into several statements in a single transaction.
+    rows.pop();
+    let inserted = session.execute_many("INSERT INTO users VALUES (?, ?)", &rows).await?;
+    assert_eq!(inserted, count as u64 / 2 * 2);
+
+    let statements: Vec<_> = server.requests().into_iter().map(|r| r.sql_text).collect();
+    assert_eq!(
+        statements,
+        [
+            "BEGIN",
+            "INSERT INTO users VALUES (?, ?)",
+            "INSERT INTO users VALUES (?, ?)",
+            "COMMIT"
+        ]
+    );
+    Ok(())
+}